@@ -0,0 +1,96 @@
+use crate::board::ChessBoard;
+use crate::pieces::Color;
+use crate::turn::Turn;
+
+/// How the computer opponent chooses its move
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Picks uniformly from the legal moves, using the given seed
+    Random(u64),
+    /// Picks the move that mates, or otherwise the move with the best material balance right
+    /// after it is played, preferring checks between moves of equal material
+    Greedy,
+}
+
+/// Picks a move for the current player with the given strategy, returning `None` if there are
+/// no legal moves
+pub fn pick_move(board: &ChessBoard, strategy: Strategy) -> Option<Turn> {
+    let moves = board.legal_moves();
+    if moves.is_empty() {
+        return None;
+    }
+    match strategy {
+        Strategy::Random(seed) => {
+            // mix in the position so the same seed does not pick the same index every turn
+            let index = splitmix64(seed ^ board.zobrist_hash()) % moves.len() as u64;
+            Some(moves[index as usize])
+        }
+        Strategy::Greedy => {
+            let sign = match board.side_to_move() {
+                Color::White => 1,
+                Color::Black => -1,
+            };
+            let mut board = board.clone();
+            moves.into_iter().max_by_key(|turn| {
+                let token = board.make_unchecked(turn);
+                let score = (
+                    board.is_checkmate(),
+                    sign * board.material_balance(),
+                    board.is_in_check(board.side_to_move()),
+                );
+                board.unmake(token);
+                score
+            })
+        }
+    }
+}
+
+/// A single step of the splitmix64 generator
+fn splitmix64(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Square;
+    use crate::turn::Move;
+
+    fn destination(turn: Option<Turn>) -> Square {
+        match turn {
+            Some(Turn::Move(Move { dst, .. })) => dst,
+            turn => panic!("expected a move, got {turn:?}"),
+        }
+    }
+
+    #[test]
+    fn greedy_takes_free_queen() {
+        let board = "4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert_eq!(destination(pick_move(&board, Strategy::Greedy)), Square::D5);
+    }
+    #[test]
+    fn greedy_prefers_mate() {
+        let board = "6k1/5ppp/8/4n3/8/2B5/8/R5K1 w - - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert_eq!(destination(pick_move(&board, Strategy::Greedy)), Square::A8);
+    }
+    #[test]
+    fn random_picks_legal_moves() {
+        let board = ChessBoard::default();
+        let turn = pick_move(&board, Strategy::Random(7)).unwrap();
+        assert!(board.legal_moves().contains(&turn));
+        assert_eq!(pick_move(&board, Strategy::Random(7)), Some(turn));
+
+        let mated = "3R2k1/5ppp/8/8/8/8/8/6K1 b - - 1 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert_eq!(pick_move(&mated, Strategy::Random(7)), None);
+        assert_eq!(pick_move(&mated, Strategy::Greedy), None);
+    }
+}
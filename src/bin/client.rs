@@ -1,35 +1,160 @@
 use std::io::{self, prelude::*, BufReader};
 use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use chess::board::ChessBoard;
+use chess::pieces::Color;
 const SERVER_ADDRESS: &str = "127.0.0.1:7878";
 
 fn main() -> io::Result<()> {
     let mut player = Player::new(SERVER_ADDRESS)?;
-    // let mut buf = String::new();
-    // io::stdin().read_line(&mut buf)?;
-    // player.send_line(&buf)?;
-    // buf.clear();
-    loop {
-        let buf = player.read_line()?;
-        print!("{buf}");
+    let messages = player.listen()?;
+    let mut color = None;
+    for line in messages {
+        let line = line.trim_end();
+        if let Some(fen) = line.strip_prefix("FEN ") {
+            let board = fen
+                .parse::<ChessBoard>()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            if color == Some(Color::Black) {
+                println!("{board:#}");
+            } else {
+                println!("{board}");
+            }
+            player.my_turn = Some(board.side_to_move()) == color;
+            if player.my_turn {
+                player.take_turn()?;
+            }
+        } else if let Some(error) = line.strip_prefix("ERROR ") {
+            // errors are only sent in reply to this client's move, so it is still their turn
+            println!("{error}");
+            player.take_turn()?;
+        } else if let Some(result) = line.strip_prefix("RESULT ") {
+            println!("Game over: {result}");
+            return Ok(());
+        } else {
+            match line {
+                "Waiting for second player..." => {
+                    println!("{line}");
+                    color = Some(Color::White);
+                    player.my_turn = true;
+                    player.take_turn()?;
+                }
+                "Connected to game!" => {
+                    println!("{line}");
+                    color = Some(Color::Black);
+                }
+                "DISCONNECTED" => {
+                    println!("Opponent disconnected");
+                    return Ok(());
+                }
+                "resign" => println!("Resigned"),
+                "draw" => player.receive_draw_offer()?,
+                _ => println!("{line}"),
+            }
+        }
     }
+    Err(io::ErrorKind::UnexpectedEof.into())
 }
 
 struct Player {
     connection: TcpStream,
+    /// Whether the server is waiting on a move from this player
+    my_turn: bool,
+    /// Whether the opponent has offered a draw that this player hasn't answered yet
+    draw_offered: bool,
 }
 
 impl Player {
     fn new(addr: impl ToSocketAddrs) -> io::Result<Player> {
         let stream = TcpStream::connect(addr)?;
-        Ok(Player { connection: stream })
+        Ok(Player {
+            connection: stream,
+            my_turn: false,
+            draw_offered: false,
+        })
+    }
+    /// Reads lines from the server on a separate thread, replying to heartbeats as they arrive so
+    /// the connection stays alive while waiting on user input
+    ///
+    /// Returns a receiver of every other line, which closes when the server disconnects
+    ///
+    /// # Errors
+    ///
+    /// returns any io errors from cloning the connection
+    fn listen(&self) -> io::Result<Receiver<String>> {
+        let reader = BufReader::new(self.connection.try_clone()?);
+        let mut heartbeat = Player {
+            connection: self.connection.try_clone()?,
+            my_turn: false,
+            draw_offered: false,
+        };
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader.lines().map_while(Result::ok) {
+                if line.trim() == "HEARTBEAT" {
+                    if heartbeat.send_line("HEARTBEAT").is_err() {
+                        return;
+                    }
+                } else if sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(receiver)
     }
-    fn read_line(&mut self) -> io::Result<String> {
-        let mut reader = BufReader::new(&mut self.connection);
-        let mut buf = String::new();
-        reader.read_line(&mut buf)?;
-        Ok(buf)
+    /// Reads lines from stdin and sends them to the server until one of them ends this player's
+    /// turn
+    ///
+    /// A move in SAN ends the turn, as does `resign` or `draw` accepting the opponent's offer,
+    /// since the server replies to those. Offering a draw doesn't get a reply, so the player is
+    /// prompted again for their move
+    ///
+    /// # Errors
+    ///
+    /// returns any io errors when reading from stdin or writing to the server, including stdin
+    /// being closed
+    fn take_turn(&mut self) -> io::Result<()> {
+        loop {
+            let line = read_input("Enter a move, `resign`, or `draw`: ")?;
+            match line.trim() {
+                "" => continue,
+                "draw" if !self.draw_offered => {
+                    self.send_line(&line)?;
+                    println!("Draw offered");
+                }
+                _ => {
+                    self.send_line(&line)?;
+                    self.my_turn = false;
+                    self.draw_offered = false;
+                    return Ok(());
+                }
+            }
+        }
     }
-    fn _send_line(&mut self, message: &str) -> io::Result<()> {
+    /// Tells the player about the opponent's draw offer
+    ///
+    /// On this player's turn the offer can be accepted from the move prompt. On the opponent's
+    /// turn the player is asked right away, since they won't be prompted until the opponent moves
+    ///
+    /// # Errors
+    ///
+    /// returns any io errors when reading from stdin or writing to the server, including stdin
+    /// being closed
+    fn receive_draw_offer(&mut self) -> io::Result<()> {
+        self.draw_offered = true;
+        if self.my_turn {
+            println!("Draw offered, send `draw` to accept");
+            return Ok(());
+        }
+        let line = read_input("Draw offered, enter `draw` to accept or nothing to keep playing: ")?;
+        if line.trim() == "draw" {
+            self.send_line(&line)?;
+        }
+        Ok(())
+    }
+    fn send_line(&mut self, message: &str) -> io::Result<()> {
         let message = if !message.ends_with('\n') {
             message.to_owned() + "\n"
         } else {
@@ -38,3 +163,18 @@ impl Player {
         self.connection.write_all(message.as_bytes())
     }
 }
+
+/// Prints a prompt and reads a line from stdin
+///
+/// # Errors
+///
+/// returns any io errors when reading from stdin, including stdin being closed
+fn read_input(prompt: &str) -> io::Result<String> {
+    print!("{prompt}");
+    io::stdout().flush()?;
+    let mut buf = String::new();
+    if io::stdin().read_line(&mut buf)? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(buf)
+}
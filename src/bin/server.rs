@@ -1,15 +1,310 @@
-use std::io::{self, prelude::*};
+use std::io::{self, prelude::*, BufReader};
+use std::iter;
 use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use chess::board::{DrawType, GameState, TurnError, Win, WinType};
+use chess::pieces::Color;
+use chess::turn::Turn;
+use chess::{ChessGame, FlagPolicy};
 const SERVER_ADDRESS: &str = "127.0.0.1:7878";
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_MISSED_HEARTBEATS: u32 = 3;
+const SPECTATOR_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 fn main() -> io::Result<()> {
     let mut players = PlayerListener::new(SERVER_ADDRESS)?;
     players.accept()?;
+    players.play(HEARTBEAT_INTERVAL)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Player {
+    One,
+    Two,
+}
+
+impl Player {
+    fn opponent(self) -> Player {
+        match self {
+            Player::One => Player::Two,
+            Player::Two => Player::One,
+        }
+    }
+    /// Player 1 plays white and player 2 plays black
+    fn color(self) -> Color {
+        match self {
+            Player::One => Color::White,
+            Player::Two => Color::Black,
+        }
+    }
+}
+
+/// Events that wake up the game loop
+enum Event<S> {
+    /// A line, or the error from failing to read one, from a player
+    Line(Player, io::Result<String>),
+    /// A new spectator that joined the game
+    Spectator(S),
+}
+
+/// Plays a game between two players until it ends, while any number of spectators watch
+///
+/// Each message from a player is a single line, and is one of:
+/// - a move in SAN, which is made on the server's game if it is legal
+/// - `resign`, which ends the game
+/// - `draw`, which offers a draw to the opponent. Sending `draw` while the opponent's offer is
+///   pending accepts it and ends the game, and making a move instead declines it
+///
+/// Every line is handled by a [Session], which describes the replies. Spectators are added to the
+/// session as they arrive, and nothing is read from them. `spectators` yields `None` while nobody
+/// is waiting to join, so that the thread taking spectators from it can be stopped and joined when
+/// the game ends.
+///
+/// `HEARTBEAT` is sent to both players every heartbeat interval, and each player must reply with
+/// `HEARTBEAT` to show that it is still connected. A player that sends nothing for
+/// [MAX_MISSED_HEARTBEATS] intervals is dropped, and `DISCONNECTED` is sent to the other player.
+/// The same happens when a player closes its connection
+///
+/// # Errors
+///
+/// returns io::ErrorKind::ConnectionAborted if a player disconnects, otherwise returns any io
+/// errors from writing to the players
+fn play_game<R, W, S>(
+    readers: [R; 2],
+    mut writers: [W; 2],
+    spectators: impl Iterator<Item = Option<S>> + Send + 'static,
+    heartbeat_interval: Duration,
+) -> io::Result<()>
+where
+    R: Read + Send + 'static,
+    W: Write,
+    S: Write + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    for (player, reader) in [Player::One, Player::Two].into_iter().zip(readers) {
+        let sender = sender.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                if sender.send(Event::Line(player, line)).is_err() {
+                    return;
+                }
+            }
+            let _ = sender.send(Event::Line(
+                player,
+                Err(io::ErrorKind::UnexpectedEof.into()),
+            ));
+        });
+    }
+    let game_over = Arc::new(AtomicBool::new(false));
+    let spectator_thread = {
+        let game_over = Arc::clone(&game_over);
+        thread::spawn(move || {
+            for spectator in spectators {
+                if game_over.load(Ordering::Relaxed) {
+                    return;
+                }
+                let Some(spectator) = spectator else {
+                    continue;
+                };
+                if sender.send(Event::Spectator(spectator)).is_err() {
+                    return;
+                }
+            }
+        })
+    };
+
+    let result = run_session(&receiver, &mut writers, heartbeat_interval);
+    game_over.store(true, Ordering::Relaxed);
+    drop(receiver);
+    let _ = spectator_thread.join();
+    result
+}
+
+/// Handles the events of a game started by [play_game] until it ends
+///
+/// # Errors
+///
+/// see [play_game]
+fn run_session<W: Write, S: Write>(
+    receiver: &Receiver<Event<S>>,
+    writers: &mut [W; 2],
+    heartbeat_interval: Duration,
+) -> io::Result<()> {
+    let mut session = Session::new();
+    let mut last_heard = [Instant::now(); 2];
+    let mut next_heartbeat = Instant::now() + heartbeat_interval;
     loop {
-        players.check_connection()?;
-        thread::sleep(Duration::from_secs(5));
+        let timeout = next_heartbeat.saturating_duration_since(Instant::now());
+        let (player, line) = match receiver.recv_timeout(timeout) {
+            Ok(Event::Line(player, line)) => (player, line),
+            Ok(Event::Spectator(spectator)) => {
+                session.add_spectator(spectator);
+                continue;
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                if let Some(player) = [Player::One, Player::Two].into_iter().find(|&player| {
+                    last_heard[player as usize].elapsed()
+                        >= heartbeat_interval * MAX_MISSED_HEARTBEATS
+                }) {
+                    return disconnect(&mut writers[player.opponent() as usize]);
+                }
+                for writer in writers.iter_mut() {
+                    send_line(writer, "HEARTBEAT")?;
+                }
+                session.spectate("HEARTBEAT");
+                next_heartbeat = Instant::now() + heartbeat_interval;
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => return Err(io::ErrorKind::NotConnected.into()),
+        };
+        let Ok(line) = line else {
+            return disconnect(&mut writers[player.opponent() as usize]);
+        };
+        last_heard[player as usize] = Instant::now();
+        if line.trim() == "HEARTBEAT" {
+            continue;
+        }
+        if session.handle_line(player, &line, writers)? {
+            return Ok(());
+        }
+    }
+}
+
+/// Notifies the remaining player that their opponent disconnected
+///
+/// # Errors
+///
+/// always returns io::ErrorKind::ConnectionAborted, failing to notify the remaining player is
+/// ignored
+fn disconnect(remaining: &mut impl Write) -> io::Result<()> {
+    let _ = send_line(remaining, "DISCONNECTED");
+    Err(io::ErrorKind::ConnectionAborted.into())
+}
+
+/// Sends a single line to a player
+///
+/// # Errors
+///
+/// returns the io error from failing to write to the client
+fn send_line(writer: &mut impl Write, message: &str) -> io::Result<()> {
+    writer.write_all(format!("{message}\n").as_bytes())
+}
+
+/// The authoritative state of a game played over the server, along with the spectators watching
+/// it
+struct Session<S> {
+    game: ChessGame,
+    draw_offer: Option<Player>,
+    spectators: Vec<S>,
+}
+
+impl<S: Write> Session<S> {
+    /// Creates a session at the starting position, where the check, capture, and checkmate flags
+    /// are optional in moves
+    fn new() -> Session<S> {
+        let mut builder = ChessGame::builder();
+        builder.flag_policy(FlagPolicy::Generate);
+        Session {
+            game: builder.build(),
+            draw_offer: None,
+            spectators: Vec::new(),
+        }
+    }
+    /// Adds a spectator to the game, sending it `FEN <fen>` of the current position
+    ///
+    /// The spectator is dropped if the write fails
+    fn add_spectator(&mut self, mut spectator: S) {
+        if send_line(&mut spectator, &format!("FEN {}", self.game.gen_fen())).is_ok() {
+            self.spectators.push(spectator);
+        }
+    }
+    /// Sends a single line to every spectator, dropping any spectator that the write fails for
+    fn spectate(&mut self, message: &str) {
+        self.spectators
+            .retain_mut(|spectator| send_line(spectator, message).is_ok());
+    }
+    /// Handles a single line sent by a player, writing the replies to the players
+    ///
+    /// - A legal move is relayed to the opponent, then `FEN <fen>` is sent to both players
+    /// - `resign` and `draw` are relayed to the opponent
+    /// - An illegal move, or a move sent out of turn, is rejected with a line starting with
+    ///   `ERROR` sent to the sender only
+    ///
+    /// Once the game ends, `RESULT <result>` is sent to both players, using the pgn result token
+    ///
+    /// Spectators receive every line that is sent to the opponent or to both players
+    ///
+    /// Returns whether the game has ended
+    ///
+    /// # Errors
+    ///
+    /// returns the io error from failing to write to either player
+    fn handle_line<W: Write>(
+        &mut self,
+        player: Player,
+        line: &str,
+        writers: &mut [W; 2],
+    ) -> io::Result<bool> {
+        let opponent = player.opponent();
+        match line.trim() {
+            "" => return Ok(false),
+            "resign" => {
+                send_line(&mut writers[opponent as usize], "resign")?;
+                self.spectate("resign");
+                self.game.game_state = GameState::Win(Win {
+                    is_white: opponent.color().is_white(),
+                    kind: WinType::Resign,
+                });
+            }
+            "draw" => {
+                send_line(&mut writers[opponent as usize], "draw")?;
+                self.spectate("draw");
+                if self.draw_offer != Some(opponent) {
+                    self.draw_offer = Some(player);
+                    return Ok(false);
+                }
+                self.game.game_state = GameState::Draw(DrawType::Agreement);
+            }
+            _ if player.color() != self.game.side_to_move() => {
+                send_line(&mut writers[player as usize], "ERROR not your turn")?;
+                return Ok(false);
+            }
+            r#move => {
+                let result = r#move
+                    .parse::<Turn>()
+                    .map_err(TurnError::InvalidNotation)
+                    .and_then(|turn| self.game.make_move(&turn));
+                if let Err(e) = result {
+                    send_line(&mut writers[player as usize], &format!("ERROR {e}"))?;
+                    return Ok(false);
+                }
+                send_line(&mut writers[opponent as usize], r#move)?;
+                self.spectate(r#move);
+                if self.draw_offer == Some(opponent) {
+                    self.draw_offer = None;
+                }
+                let fen = format!("FEN {}", self.game.gen_fen());
+                for writer in writers.iter_mut() {
+                    send_line(writer, &fen)?;
+                }
+                self.spectate(&fen);
+            }
+        }
+
+        let Some(outcome) = self.game.outcome() else {
+            return Ok(false);
+        };
+        let result = format!("RESULT {}", outcome.pgn_result_token());
+        for writer in writers.iter_mut() {
+            send_line(writer, &result)?;
+        }
+        self.spectate(&result);
+        Ok(true)
     }
 }
 
@@ -27,7 +322,6 @@ impl PlayerListener {
     /// returns any io error when binding to the address
     fn new(addr: &str) -> io::Result<PlayerListener> {
         let listener = TcpListener::bind(addr)?;
-        // listener.set_nonblocking(true)?;
         Ok(PlayerListener {
             listener,
             player1: None,
@@ -36,7 +330,8 @@ impl PlayerListener {
     }
     /// Accepts two connections and stores them in the PlayerListener
     ///
-    /// Blocks until both connections are established
+    /// Blocks until both connections are established. Writes to either connection time out
+    /// after [HEARTBEAT_INTERVAL], so an unresponsive client can't hang the server
     ///
     /// # Errors
     ///
@@ -44,12 +339,20 @@ impl PlayerListener {
     fn accept(&mut self) -> io::Result<()> {
         println!("Waiting for player 1");
         self.player1 = Some(self.listener.accept()?.0);
+        self.player1
+            .as_mut()
+            .unwrap()
+            .set_write_timeout(Some(HEARTBEAT_INTERVAL))?;
         self.player1
             .as_mut()
             .unwrap()
             .write_all(b"Waiting for second player...\n")?;
         println!("Waiting for player 2");
         self.player2 = Some(self.listener.accept()?.0);
+        self.player2
+            .as_mut()
+            .unwrap()
+            .set_write_timeout(Some(HEARTBEAT_INTERVAL))?;
         self.player2
             .as_mut()
             .unwrap()
@@ -57,34 +360,305 @@ impl PlayerListener {
         println!("Fully connected");
         Ok(())
     }
-    /// This function checks the connection status of both players.
+    /// Plays a game between the two connected players with [play_game]
     ///
-    /// # Errors
+    /// Connections made while the game is in progress join as spectators. A client can tell its
+    /// role from the first line it receives: `Waiting for second player...` for player 1,
+    /// `Connected to game!` for player 2, and `Spectating game!` for a spectator. A spectator then
+    /// receives `FEN <fen>` of the current position, followed by the moves and `FEN` and `RESULT`
+    /// lines of the game. Anything sent by a spectator is ignored
     ///
-    /// If the players are unitialized (None) this function returns io::ErrorKind::Other,
-    /// otherwise this function returns the the io error from failing to read from the client.
+    /// # Errors
     ///
-    /// If this function encounters an error, it will close the connection.
-    fn check_connection(&mut self) -> io::Result<()> {
-        if self.player1.is_none() || self.player2.is_none() {
-            return Err(io::ErrorKind::Other.into());
+    /// If the players are unitialized (None) this function returns io::ErrorKind::NotConnected,
+    /// otherwise this function returns any io errors from reading from or writing to the clients
+    fn play(&mut self, heartbeat_interval: Duration) -> io::Result<()> {
+        let (Some(player1), Some(player2)) = (&self.player1, &self.player2) else {
+            return Err(io::ErrorKind::NotConnected.into());
+        };
+        // the listener is polled so that the spectator thread notices when the game ends
+        let listener = self.listener.try_clone()?;
+        listener.set_nonblocking(true)?;
+        let spectators = iter::repeat_with(move || match listener.accept() {
+            Ok((stream, _)) => Ok(Some(stream)),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(SPECTATOR_POLL_INTERVAL);
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        })
+        .map_while(Result::ok)
+        .map(|stream| {
+            stream.and_then(|mut stream| {
+                stream.set_nonblocking(false).ok()?;
+                stream.set_write_timeout(Some(HEARTBEAT_INTERVAL)).ok()?;
+                stream.write_all(b"Spectating game!\n").ok()?;
+                Some(stream)
+            })
+        });
+        let result = play_game(
+            [player1.try_clone()?, player2.try_clone()?],
+            [player1, player2],
+            spectators,
+            heartbeat_interval,
+        );
+        self.listener.set_nonblocking(false)?;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+
+    const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    fn take_output(writers: &mut [Vec<u8>; 2]) -> [String; 2] {
+        writers
+            .each_mut()
+            .map(|writer| String::from_utf8(std::mem::take(writer)).unwrap())
+    }
+
+    #[test]
+    fn session_moves() {
+        let mut session: Session<Vec<u8>> = Session::new();
+        let mut writers = [Vec::new(), Vec::new()];
+
+        assert!(!session
+            .handle_line(Player::Two, "e5", &mut writers)
+            .unwrap());
+        assert_eq!(
+            take_output(&mut writers),
+            [String::new(), String::from("ERROR not your turn\n")]
+        );
+        assert!(!session
+            .handle_line(Player::One, "Ke2", &mut writers)
+            .unwrap());
+        let [one, two] = take_output(&mut writers);
+        assert!(one.starts_with("ERROR "));
+        assert_eq!(two, "");
+        assert!(!session
+            .handle_line(Player::One, "x?", &mut writers)
+            .unwrap());
+        let [one, _] = take_output(&mut writers);
+        assert_eq!(one, "ERROR That move isn't valid chess notation\n");
+        assert!(!session
+            .handle_line(Player::One, "--", &mut writers)
+            .unwrap());
+        let [one, _] = take_output(&mut writers);
+        assert_eq!(one, "ERROR Null moves aren't allowed in this game\n");
+        assert_eq!(session.game.gen_fen(), START_FEN);
+
+        assert!(!session
+            .handle_line(Player::One, "e4", &mut writers)
+            .unwrap());
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        assert_eq!(
+            take_output(&mut writers),
+            [format!("FEN {fen}\n"), format!("e4\nFEN {fen}\n")]
+        );
+
+        for (player, r#move) in [
+            (Player::Two, "e5"),
+            (Player::One, "Qh5"),
+            (Player::Two, "Nc6"),
+            (Player::One, "Bc4"),
+            (Player::Two, "Nf6"),
+        ] {
+            assert!(!session.handle_line(player, r#move, &mut writers).unwrap());
         }
+        take_output(&mut writers);
+        assert!(session
+            .handle_line(Player::One, "Qxf7", &mut writers)
+            .unwrap());
+        let [one, two] = take_output(&mut writers);
+        assert!(one.ends_with("RESULT 1-0\n"));
+        assert!(two.starts_with("Qxf7\n"));
+        assert!(two.ends_with("RESULT 1-0\n"));
+    }
+    #[test]
+    fn session_resign_and_draw() {
+        let mut session: Session<Vec<u8>> = Session::new();
+        let mut writers = [Vec::new(), Vec::new()];
+        assert!(session
+            .handle_line(Player::One, "resign", &mut writers)
+            .unwrap());
+        assert_eq!(
+            take_output(&mut writers),
+            [
+                String::from("RESULT 0-1\n"),
+                String::from("resign\nRESULT 0-1\n")
+            ]
+        );
 
-        match self.player1.as_mut().unwrap().write_all(b"HEARTBEAT\n") {
-            Ok(_) => (),
-            Err(e) => {
-                self.player1.take();
-                return Err(e);
+        let mut session: Session<Vec<u8>> = Session::new();
+        assert!(!session
+            .handle_line(Player::Two, "draw", &mut writers)
+            .unwrap());
+        assert!(!session
+            .handle_line(Player::One, "e4", &mut writers)
+            .unwrap());
+        // moving declines the pending offer
+        assert!(!session
+            .handle_line(Player::One, "draw", &mut writers)
+            .unwrap());
+        take_output(&mut writers);
+        assert!(session
+            .handle_line(Player::Two, "draw", &mut writers)
+            .unwrap());
+        assert_eq!(
+            take_output(&mut writers),
+            [
+                String::from("draw\nRESULT 1/2-1/2\n"),
+                String::from("RESULT 1/2-1/2\n")
+            ]
+        );
+    }
+    #[test]
+    fn session_spectators() {
+        /// Spectator whose writes fail once it has disconnected
+        struct Spectator {
+            output: Vec<u8>,
+            connected: bool,
+        }
+        impl Write for Spectator {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if !self.connected {
+                    return Err(io::ErrorKind::BrokenPipe.into());
+                }
+                self.output.write(buf)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
             }
         }
-        match self.player2.as_mut().unwrap().write_all(b"HEARTBEAT\n") {
-            Ok(_) => (),
-            Err(e) => {
-                self.player2.take();
-                return Err(e);
+        fn spectator(connected: bool) -> Spectator {
+            Spectator {
+                output: Vec::new(),
+                connected,
             }
         }
 
-        Ok(())
+        let mut session = Session::new();
+        let mut writers = [Vec::new(), Vec::new()];
+        session.add_spectator(spectator(true));
+        session.add_spectator(spectator(false));
+        assert_eq!(session.spectators.len(), 1);
+        assert!(!session
+            .handle_line(Player::One, "e4", &mut writers)
+            .unwrap());
+        session.add_spectator(spectator(true));
+        assert!(!session
+            .handle_line(Player::Two, "e5", &mut writers)
+            .unwrap());
+
+        // a spectator disconnecting doesn't affect the game
+        session.spectators[0].connected = false;
+        assert!(!session
+            .handle_line(Player::One, "Nf3", &mut writers)
+            .unwrap());
+        assert_eq!(session.spectators.len(), 1);
+        assert!(session
+            .handle_line(Player::Two, "resign", &mut writers)
+            .unwrap());
+
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        let output = String::from_utf8(session.spectators.remove(0).output).unwrap();
+        assert!(output.starts_with(&format!("FEN {fen}\ne5\nFEN ")));
+        assert!(output.ends_with("Nf3\nFEN rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2\nresign\nRESULT 1-0\n"));
+    }
+
+    type Client = (TcpStream, BufReader<TcpStream>);
+
+    /// Starts a server on an ephemeral port and connects both players to it
+    fn start_server(
+        heartbeat_interval: Duration,
+    ) -> (
+        thread::JoinHandle<io::Result<()>>,
+        SocketAddr,
+        Client,
+        Client,
+    ) {
+        let mut players = PlayerListener::new("127.0.0.1:0").unwrap();
+        let addr = players.listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            players.accept()?;
+            players.play(heartbeat_interval)
+        });
+
+        let (white, mut white_reader) = connect(addr);
+        assert_eq!(
+            read_line(&mut white_reader),
+            "Waiting for second player...\n"
+        );
+        let (black, mut black_reader) = connect(addr);
+        assert_eq!(read_line(&mut black_reader), "Connected to game!\n");
+        (server, addr, (white, white_reader), (black, black_reader))
+    }
+    fn connect(addr: SocketAddr) -> Client {
+        let stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let reader = BufReader::new(stream.try_clone().unwrap());
+        (stream, reader)
+    }
+    fn read_line(reader: &mut BufReader<TcpStream>) -> String {
+        let mut buf = String::new();
+        reader.read_line(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn play_over_sockets() {
+        let (server, addr, (mut white, mut white_reader), (mut black, mut black_reader)) =
+            start_server(HEARTBEAT_INTERVAL);
+        let (mut spectator, mut spectator_reader) = connect(addr);
+        assert_eq!(read_line(&mut spectator_reader), "Spectating game!\n");
+        assert!(read_line(&mut spectator_reader).starts_with("FEN "));
+        // spectators can't make moves
+        spectator.write_all(b"e4\n").unwrap();
+
+        white.write_all(b"e4\n").unwrap();
+        assert_eq!(read_line(&mut black_reader), "e4\n");
+        assert!(read_line(&mut black_reader).starts_with("FEN "));
+        assert!(read_line(&mut white_reader).starts_with("FEN "));
+        assert_eq!(read_line(&mut spectator_reader), "e4\n");
+        assert!(read_line(&mut spectator_reader).starts_with("FEN "));
+        black.write_all(b"resign\n").unwrap();
+        assert_eq!(read_line(&mut white_reader), "resign\n");
+        assert_eq!(read_line(&mut white_reader), "RESULT 1-0\n");
+        assert_eq!(read_line(&mut black_reader), "RESULT 1-0\n");
+        assert_eq!(read_line(&mut spectator_reader), "resign\n");
+        assert_eq!(read_line(&mut spectator_reader), "RESULT 1-0\n");
+        server.join().unwrap().unwrap();
+    }
+    #[test]
+    fn closed_connection() {
+        let (server, _, white, (_black, mut black_reader)) = start_server(HEARTBEAT_INTERVAL);
+        drop(white);
+        assert_eq!(read_line(&mut black_reader), "DISCONNECTED\n");
+        assert_eq!(
+            server.join().unwrap().unwrap_err().kind(),
+            io::ErrorKind::ConnectionAborted
+        );
+    }
+    #[test]
+    fn missed_heartbeats() {
+        let (server, _, (mut white, mut white_reader), _black) =
+            start_server(Duration::from_millis(20));
+        // white keeps replying to heartbeats while black stays silent
+        let heartbeats = thread::spawn(move || loop {
+            match read_line(&mut white_reader).as_str() {
+                "HEARTBEAT\n" => white.write_all(b"HEARTBEAT\n").unwrap(),
+                line => return String::from(line),
+            }
+        });
+        assert_eq!(heartbeats.join().unwrap(), "DISCONNECTED\n");
+        assert_eq!(
+            server.join().unwrap().unwrap_err().kind(),
+            io::ErrorKind::ConnectionAborted
+        );
     }
 }
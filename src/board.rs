@@ -1,19 +1,30 @@
+mod attacks;
 mod default;
 mod line;
 mod source;
 mod square;
+mod zobrist;
 pub use line::Line;
 pub use source::Source;
 pub use square::Square;
 
-use std::collections::HashMap;
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::cmp::Ordering;
+use core::fmt::{self, Display};
+use core::str::FromStr;
+#[cfg(feature = "std")]
 use std::error::Error;
-use std::fmt::{self, Display};
-use std::str::FromStr;
 
-use crate::pieces::{Piece, PieceType};
+use crate::parser::ChessParseError;
+use crate::pieces::{Color, Piece, PieceType};
 use crate::turn::{flags, CastlingType, Move, Turn};
 use crate::utils::Counter;
+use crate::RotateBoard;
 
 #[derive(Debug)]
 /// Ways that a turn can be incorrect
@@ -54,9 +65,25 @@ pub enum TurnError {
     RemoveCheckSpecifier,
     /// Need to remove the `x` flag for a non-capture
     RemoveCaptureSpecifier,
+    /// The move couldn't be parsed from the given notation
+    InvalidNotation(ChessParseError),
+    /// Cannot pass the turn with a null move while in check
+    NullMoveInCheck,
+    /// Null moves aren't allowed in the game
+    NullMoveNotAllowed,
+    /// A pawn can't promote into a king or another pawn
+    InvalidPromotion(PieceType),
 }
 
-impl Error for TurnError {}
+#[cfg(feature = "std")]
+impl Error for TurnError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TurnError::InvalidNotation(err) => Some(err),
+            _ => None,
+        }
+    }
+}
 impl Display for TurnError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -90,26 +117,76 @@ impl Display for TurnError {
             TurnError::RemoveCheckmateSpecifier => {
                 write!(f, "Remove `#` when not giving checkmate")
             }
-            TurnError::RemoveCheckSpecifier => write!(f, "Remove `+` when capturing a piece"),
+            TurnError::RemoveCheckSpecifier => write!(f, "Remove `+` when not giving check"),
             TurnError::RemoveCaptureSpecifier => write!(f, "Remove `x` when not capturing a piece"),
+            TurnError::InvalidNotation(_) => write!(f, "That move isn't valid chess notation"),
+            TurnError::NullMoveInCheck => write!(f, "Can't pass the turn while in check"),
+            TurnError::NullMoveNotAllowed => write!(f, "Null moves aren't allowed in this game"),
+            TurnError::InvalidPromotion(piece) => {
+                write!(f, "Cannot promote a pawn into a {piece}")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Ways that a position can be impossible to reach in a legal game
+pub enum PositionError {
+    /// The player has no king
+    MissingKing(Color),
+    /// The player has more than one king
+    TooManyKings(Color),
+    /// There is a pawn on the first or eighth rank
+    PawnOnBackRank(Square),
+    /// The player who just moved is in check
+    OppositeKingInCheck,
+}
+
+impl PositionError {
+    fn reason(&self) -> &'static str {
+        match self {
+            PositionError::MissingKing(Color::White) => "White has no king",
+            PositionError::MissingKing(Color::Black) => "Black has no king",
+            PositionError::TooManyKings(Color::White) => "White has more than one king",
+            PositionError::TooManyKings(Color::Black) => "Black has more than one king",
+            PositionError::PawnOnBackRank(_) => "A pawn is on the first or eighth rank",
+            PositionError::OppositeKingInCheck => "The player who just moved is in check",
         }
     }
 }
 
+#[cfg(feature = "std")]
+impl Error for PositionError {}
+impl Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.reason())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 /// Stores the current board state
 ///
 /// Includes the piece locations, current turn, castling rights, en passant rights, half move
 /// clock, and the full move number
 pub struct ChessBoard {
-    piece_locs: HashMap<Square, Piece>,
-    is_white: bool,
+    piece_locs: [Option<Piece>; 64],
+    side_to_move: Color,
     castling: CastlingRights,
     en_passant: Option<Square>,
     half_move_clock: u8,
     full_move_number: u16,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// The parts of a [ChessBoard] that make up a position, which are the piece locations, current
+/// turn, castling rights, and en passant rights, created by [ChessBoard::positional_key]
+pub struct PositionKey {
+    piece_locs: [Option<Piece>; 64],
+    side_to_move: Color,
+    castling: CastlingRights,
+    en_passant: Option<Square>,
+}
+
 impl ChessBoard {
     /// Returns the fully qualified turn by determining the source of the piece
     ///
@@ -119,6 +196,10 @@ impl ChessBoard {
     pub fn validate_and_complete_turn(&self, turn: Turn) -> Result<Turn, TurnError> {
         match turn {
             Turn::Move(r#move) => {
+                // the same rule as the parser, for turns built without parsing
+                if let Some(piece @ (PieceType::King | PieceType::Pawn)) = r#move.promotion {
+                    return Err(TurnError::InvalidPromotion(piece));
+                }
                 let src = Some(self.validate_move(&r#move)?);
                 Ok(Turn::Move(Move { src, ..r#move }))
             }
@@ -126,8 +207,50 @@ impl ChessBoard {
                 self.validate_castling(&castling, &flags)?;
                 Ok(turn)
             }
+            Turn::Null if self.is_in_check(self.side_to_move) => Err(TurnError::NullMoveInCheck),
+            Turn::Null => Ok(turn),
         }
     }
+    /// Returns the board after the turn is played, validating and completing the turn first and
+    /// leaving the current board unchanged
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the turn is illegal, the same as
+    /// [ChessBoard::validate_and_complete_turn]
+    pub fn try_move(&self, turn: &Turn) -> Result<ChessBoard, TurnError> {
+        let full_turn = self.validate_and_complete_turn(*turn)?;
+        let mut board = self.clone();
+        board.update_board(&full_turn);
+        Ok(board)
+    }
+    /// Parses a move in algebraic notation, then validates, completes, and flags it, returning a
+    /// turn with a fully qualified [Source::Square] as the source
+    ///
+    /// # Errors
+    ///
+    /// Returns [crate::Error::Parse] if the move can't be parsed, and [crate::Error::Turn] if it is
+    /// illegal
+    pub fn san_to_turn(&self, san: &str) -> Result<Turn, crate::Error> {
+        let turn = san.parse::<Turn>()?;
+        Ok(self.gen_flags(self.validate_and_complete_turn(turn)?))
+    }
+    /// Parses a move in algebraic notation, then validates, completes, and flags it before playing
+    /// it, returning the turn that was played
+    ///
+    /// # Side effects
+    ///
+    /// On success, updates the board the same as [ChessBoard::update_board]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the move can't be parsed or is illegal
+    #[cfg(feature = "std")]
+    pub fn apply_san(&mut self, san: &str) -> Result<Turn, Box<dyn Error>> {
+        let full_turn = self.san_to_turn(san)?;
+        self.update_board(&full_turn);
+        Ok(full_turn)
+    }
     /// Updates the piece locations given a fully qualified turn with the source square specified
     ///
     /// # Side effects
@@ -139,125 +262,37 @@ impl ChessBoard {
     ///
     /// Panics if the turn is a move and does not have [Source::Square] as the source
     pub fn update_board(&mut self, turn: &Turn) {
+        // must be checked before the pieces are moved
+        let resets_clock = match turn {
+            Turn::Castling(_, _) | Turn::Null => false,
+            Turn::Move(r#move) => {
+                r#move.piece == PieceType::Pawn || self.get(&r#move.dst).is_some()
+            }
+        };
         match turn {
             Turn::Castling(castling_type, _) => {
-                let new_king;
-                let new_rook;
-                let old_king_loc: Square;
-                let old_rook_loc: Square;
-                match (castling_type, self.is_white) {
-                    (CastlingType::Long, true) => {
-                        new_king = (
-                            Square::C1,
-                            Piece {
-                                piece: PieceType::King,
-                                is_white: true,
-                            },
-                        );
-                        new_rook = (
-                            Square::D1,
-                            Piece {
-                                piece: PieceType::Rook,
-                                is_white: true,
-                            },
-                        );
-                        old_king_loc = Square::E1;
-                        old_rook_loc = Square::A1;
-                        self.castling.white_kingside = false;
-                        self.castling.white_queenside = false;
-                    }
-                    (CastlingType::Long, false) => {
-                        new_king = (
-                            Square::C8,
-                            Piece {
-                                piece: PieceType::King,
-                                is_white: false,
-                            },
-                        );
-                        new_rook = (
-                            Square::D8,
-                            Piece {
-                                piece: PieceType::Rook,
-                                is_white: false,
-                            },
-                        );
-                        old_king_loc = Square::E8;
-                        old_rook_loc = Square::A8;
-                        self.castling.black_kingside = false;
-                        self.castling.black_queenside = false;
-                    }
-                    (CastlingType::Short, true) => {
-                        new_king = (
-                            Square::G1,
-                            Piece {
-                                piece: PieceType::King,
-                                is_white: true,
-                            },
-                        );
-                        new_rook = (
-                            Square::F1,
-                            Piece {
-                                piece: PieceType::Rook,
-                                is_white: true,
-                            },
-                        );
-                        old_king_loc = Square::E1;
-                        old_rook_loc = Square::H1;
-                        self.castling.white_kingside = false;
-                        self.castling.white_queenside = false;
-                    }
-                    (CastlingType::Short, false) => {
-                        new_king = (
-                            Square::G8,
-                            Piece {
-                                piece: PieceType::King,
-                                is_white: false,
-                            },
-                        );
-                        new_rook = (
-                            Square::F8,
-                            Piece {
-                                piece: PieceType::Rook,
-                                is_white: false,
-                            },
-                        );
-                        old_king_loc = Square::E8;
-                        old_rook_loc = Square::H8;
-                        self.castling.black_kingside = false;
-                        self.castling.black_queenside = false;
-                    }
+                let ((king_src, king_dst), (rook_src, rook_dst)) =
+                    self.castling_squares(self.side_to_move, castling_type);
+                if self.side_to_move.is_white() {
+                    self.castling.white_kingside = false;
+                    self.castling.white_queenside = false;
+                } else {
+                    self.castling.black_kingside = false;
+                    self.castling.black_queenside = false;
                 }
-                self.remove(&old_king_loc);
-                self.remove(&old_rook_loc);
-                self.insert(new_king);
-                self.insert(new_rook);
+                // both are removed first, as the king or rook may land on the other's square
+                self.remove(&king_src);
+                self.remove(&rook_src);
+                self.insert((king_dst, Piece::new(PieceType::King, self.side_to_move)));
+                self.insert((rook_dst, Piece::new(PieceType::Rook, self.side_to_move)));
+                self.en_passant = None;
             }
             Turn::Move(r#move) => {
                 let Some(Source::Square(src)) = r#move.src else {
                     panic!("No specified source");
                 };
-                match src {
-                    Square::A1 => self.castling.white_queenside = false,
-                    Square::E1 => {
-                        self.castling.white_kingside = false;
-                        self.castling.white_queenside = false;
-                    }
-                    Square::H1 => self.castling.white_kingside = false,
-                    Square::A8 => self.castling.black_queenside = false,
-                    Square::E8 => {
-                        self.castling.black_kingside = false;
-                        self.castling.black_queenside = false;
-                    }
-                    Square::H8 => self.castling.black_kingside = false,
-                    _ => (),
-                };
-                match r#move.dst {
-                    Square::A1 => self.castling.white_queenside = false,
-                    Square::H1 => self.castling.white_kingside = false,
-                    Square::A8 => self.castling.black_queenside = false,
-                    Square::H8 => self.castling.black_kingside = false,
-                    _ => (),
-                };
+                self.castling.revoke_for_square(src, true);
+                self.castling.revoke_for_square(r#move.dst, false);
                 let piece = (
                     r#move.dst,
                     Piece {
@@ -266,15 +301,17 @@ impl ChessBoard {
                         } else {
                             r#move.piece
                         },
-                        is_white: self.is_white,
+                        color: self.side_to_move,
                     },
                 );
 
+                let is_en_passant = self.is_en_passant(turn);
+
                 // update the board
                 self.remove(&src);
                 self.insert(piece);
-                if self.en_passant.is_some_and(|sq| sq == r#move.dst) {
-                    if self.is_white {
+                if is_en_passant {
+                    if self.side_to_move.is_white() {
                         self.remove(&r#move.dst.down().expect("is valid square"));
                     } else {
                         self.remove(&r#move.dst.up().expect("is valid square"));
@@ -283,53 +320,134 @@ impl ChessBoard {
 
                 // update en passant
                 if r#move.piece == PieceType::Pawn
-                    && if self.is_white {
-                        Line::Rank4.to_vec().contains(&r#move.dst)
-                            && Line::Rank2.to_vec().contains(&src)
+                    && if self.side_to_move.is_white() {
+                        r#move.dst.rank() == Line::Rank4 && src.rank() == Line::Rank2
                     } else {
-                        Line::Rank5.to_vec().contains(&r#move.dst)
-                            && Line::Rank7.to_vec().contains(&src)
+                        r#move.dst.rank() == Line::Rank5 && src.rank() == Line::Rank7
                     }
                 {
-                    self.en_passant = if self.is_white { src.up() } else { src.down() };
+                    self.en_passant = if self.side_to_move.is_white() {
+                        src.up()
+                    } else {
+                        src.down()
+                    };
                 } else {
                     self.en_passant = None;
                 }
             }
+            Turn::Null => self.en_passant = None,
         }
         // update fifty move rule
-        if let Turn::Move(r#move) = turn {
-            if r#move.piece == PieceType::Pawn || self.get(&r#move.dst).is_some() {
-                self.half_move_clock = 0;
-            } else {
-                self.half_move_clock += 1;
-            }
+        if resets_clock {
+            self.half_move_clock = 0;
+        } else {
+            self.half_move_clock = self.half_move_clock.saturating_add(1);
         }
-        if !self.is_white {
+        if !self.side_to_move.is_white() {
             self.full_move_number += 1;
         }
-        self.is_white = !self.is_white;
+        self.side_to_move = self.side_to_move.opposite();
+    }
+    /// Makes the turn without checking that it is legal, returning the token that is needed to
+    /// unmake it with [ChessBoard::unmake]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the turn is a move and does not have [Source::Square] as the source
+    pub fn make_unchecked(&mut self, turn: &Turn) -> UndoToken {
+        let (moved_piece, captured) = match turn {
+            Turn::Castling(_, _) | Turn::Null => (None, None),
+            Turn::Move(r#move) => {
+                let Some(Source::Square(src)) = r#move.src else {
+                    panic!("No specified source");
+                };
+                let captured_loc = if self.is_en_passant(turn) {
+                    if self.side_to_move.is_white() {
+                        r#move.dst.down().expect("is valid square")
+                    } else {
+                        r#move.dst.up().expect("is valid square")
+                    }
+                } else {
+                    r#move.dst
+                };
+                (
+                    self.get(&src).copied(),
+                    self.get(&captured_loc).map(|pc| (captured_loc, *pc)),
+                )
+            }
+        };
+        let token = UndoToken {
+            turn: *turn,
+            moved_piece,
+            captured,
+            castling: self.castling,
+            en_passant: self.en_passant,
+            half_move_clock: self.half_move_clock,
+        };
+        self.update_board(turn);
+        token
+    }
+    /// Unmakes a turn made with [ChessBoard::make_unchecked], restoring the exact previous state
+    ///
+    /// The token must come from the last turn made on this board
+    pub fn unmake(&mut self, token: UndoToken) {
+        self.side_to_move = self.side_to_move.opposite();
+        if !self.side_to_move.is_white() {
+            self.full_move_number -= 1;
+        }
+        match token.turn {
+            Turn::Castling(castling_type, _) => {
+                let ((king_src, king_dst), (rook_src, rook_dst)) =
+                    self.castling_squares(self.side_to_move, &castling_type);
+                self.remove(&king_dst);
+                self.remove(&rook_dst);
+                self.insert((king_src, Piece::new(PieceType::King, self.side_to_move)));
+                self.insert((rook_src, Piece::new(PieceType::Rook, self.side_to_move)));
+            }
+            Turn::Move(r#move) => {
+                let Some(Source::Square(src)) = r#move.src else {
+                    panic!("No specified source");
+                };
+                self.remove(&r#move.dst);
+                if let Some(piece) = token.moved_piece {
+                    self.insert((src, piece));
+                }
+                if let Some(captured) = token.captured {
+                    self.insert(captured);
+                }
+            }
+            Turn::Null => (),
+        }
+        self.castling = token.castling;
+        self.en_passant = token.en_passant;
+        self.half_move_clock = token.half_move_clock;
     }
     /// Returns what the gamestate is based on the board state and the position history
     ///
     /// The current player must be the player who will play next, rather than the player who just
     /// made the move, so this function must be run after [ChessBoard::update_board]
-    pub fn check_gamestate(&self, position_hist: &Counter<String>) -> GameState {
+    pub fn check_gamestate(&self, position_hist: &Counter<u64>) -> GameState {
         let mut moves: Vec<Turn> = Vec::new();
-        for pc in self.get_player_pieces(self.is_white) {
+        for pc in self.get_player_pieces(self.side_to_move) {
             let this_piece_moves = self.gen_moves(pc);
             for dst in this_piece_moves {
                 let new_turn = Turn::new((*pc.0, *pc.1), dst);
                 moves.push(new_turn);
             }
         }
+        for castling_type in [CastlingType::Short, CastlingType::Long] {
+            if self.validate_castling(&castling_type, &flags::NONE).is_ok() {
+                moves.push(Turn::Castling(castling_type, flags::NONE));
+            }
+        }
+        let mut test_board = self.clone();
         let no_moves_left = moves
             .iter()
-            .all(|turn| self.causes_check(turn, self.is_white));
+            .all(|turn| test_board.causes_check(turn, self.side_to_move));
 
         // checkmate and stalemate
-        if no_moves_left && self.is_in_check(self.is_white) {
-            if !self.is_white {
+        if no_moves_left && self.is_in_check(self.side_to_move) {
+            if !self.side_to_move.is_white() {
                 return GameState::Win(Win {
                     is_white: true,
                     kind: WinType::Checkmate,
@@ -349,25 +467,175 @@ impl ChessBoard {
             return GameState::Draw(DrawType::InsufficientMaterial);
         }
 
-        if self.is_threefold_repitition(position_hist) {
-            return GameState::Draw(DrawType::ThreefoldRepitition);
+        if self.repetition_count(position_hist) >= 5 {
+            return GameState::Draw(DrawType::FivefoldRepetition);
         }
 
-        if self.half_move_clock >= 100 {
-            return GameState::Draw(DrawType::FiftyMove);
+        if self.half_move_clock >= 150 {
+            return GameState::Draw(DrawType::SeventyFiveMove);
         }
 
         GameState::Continue
     }
+    /// Returns the draw that the current player can claim, based on the board state and the
+    /// position history, or `None` if no draw can be claimed
+    ///
+    /// Threefold repetition and the fifty move rule must be claimed, unlike the draws returned
+    /// by [ChessBoard::check_gamestate]
+    pub fn claimable_draw(&self, position_hist: &Counter<u64>) -> Option<DrawType> {
+        if self.repetition_count(position_hist) >= 3 {
+            Some(DrawType::ThreefoldRepetition)
+        } else if self.half_move_clock >= 100 {
+            Some(DrawType::FiftyMove)
+        } else {
+            None
+        }
+    }
+    /// Returns every legal turn for the current player, including castling
+    ///
+    /// Each returned move has a fully qualified [Source::Square] as the source
+    pub fn legal_moves(&self) -> Vec<Turn> {
+        let mut moves: Vec<Turn> = Vec::new();
+        let mut test_board = self.clone();
+        for pc in self.get_player_pieces(self.side_to_move) {
+            for dst in self.gen_moves(pc) {
+                let turn = Turn::new((*pc.0, *pc.1), dst);
+                if test_board.causes_check(&turn, self.side_to_move) {
+                    continue;
+                }
+                match turn {
+                    // the promoted piece doesn't change whether the king is left in check
+                    Turn::Move(r#move)
+                        if r#move.piece == PieceType::Pawn
+                            && matches!(dst.rank(), Line::Rank1 | Line::Rank8) =>
+                    {
+                        moves.extend(PieceType::PROMOTABLE.map(|promotion| {
+                            Turn::Move(Move {
+                                promotion: Some(promotion),
+                                ..r#move
+                            })
+                        }));
+                    }
+                    _ => moves.push(turn),
+                }
+            }
+        }
+        for castling_type in [CastlingType::Short, CastlingType::Long] {
+            if self.validate_castling(&castling_type, &flags::NONE).is_ok() {
+                moves.push(Turn::Castling(castling_type, flags::NONE));
+            }
+        }
+        moves
+    }
+    /// Returns every square the piece on the given square can legally move to, including the
+    /// king's destination when castling. Returns an empty vec if the square is empty or holds a
+    /// piece of the player who isn't moving
+    pub fn legal_destinations(&self, from: Square) -> Vec<Square> {
+        let Some(&piece) = self.get(&from) else {
+            return Vec::new();
+        };
+        if piece.color != self.side_to_move {
+            return Vec::new();
+        }
+        let mut test_board = self.clone();
+        let mut destinations: Vec<Square> = self
+            .gen_moves((&from, &piece))
+            .into_iter()
+            .filter(|&dst| !test_board.causes_check(&Turn::new((from, piece), dst), piece.color))
+            .collect();
+        if piece.piece == PieceType::King {
+            for castling_type in [CastlingType::Short, CastlingType::Long] {
+                if self
+                    .validate_castling(&castling_type, &flags::NONE)
+                    .is_err()
+                {
+                    continue;
+                }
+                let ((_, king_dst), _) = self.castling_squares(piece.color, &castling_type);
+                if !destinations.contains(&king_dst) {
+                    destinations.push(king_dst);
+                }
+            }
+        }
+        destinations
+    }
+    /// Returns the number of leaf nodes in the legal move tree of the given depth
+    ///
+    /// Used to check the correctness of the move generation against known values
+    pub fn perft(&self, depth: u32) -> u64 {
+        self.clone().perft_in_place(depth)
+    }
+    fn perft_in_place(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        self.legal_moves()
+            .iter()
+            .map(|turn| {
+                let token = self.make_unchecked(turn);
+                let nodes = self.perft_in_place(depth - 1);
+                self.unmake(token);
+                nodes
+            })
+            .sum()
+    }
+    /// Returns the player whose turn it is
+    pub fn side_to_move(&self) -> Color {
+        self.side_to_move
+    }
     /// Returns whether the current player is white
+    #[deprecated(note = "use `ChessBoard::side_to_move` instead")]
     pub fn is_white(&self) -> bool {
-        self.is_white
+        self.side_to_move.is_white()
+    }
+    /// Returns the square that can be captured en passant onto, if the last move was a double
+    /// pawn push
+    pub fn en_passant_square(&self) -> Option<Square> {
+        self.en_passant
+    }
+    /// Returns the castling rights of both players
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.castling
+    }
+    /// Returns the number of half moves since the last capture or pawn move
+    pub fn half_move_clock(&self) -> u8 {
+        self.half_move_clock
+    }
+    /// Returns the full move number, starting at 1 and incremented after black's move
+    pub fn full_move_number(&self) -> u16 {
+        self.full_move_number
+    }
+    /// Checks that the position could be reached in a legal game
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either player doesn't have exactly one king, a pawn is on the first or
+    /// eighth rank, or the player who just moved is in check
+    pub fn validate_position(&self) -> Result<(), PositionError> {
+        for color in [Color::White, Color::Black] {
+            match self.find_pieces(Piece::new(PieceType::King, color)).count() {
+                0 => return Err(PositionError::MissingKing(color)),
+                1 => (),
+                _ => return Err(PositionError::TooManyKings(color)),
+            }
+        }
+        if let Some((sq, _)) = self.pieces().find(|(sq, pc)| {
+            pc.piece == PieceType::Pawn && matches!(sq.rank(), Line::Rank1 | Line::Rank8)
+        }) {
+            return Err(PositionError::PawnOnBackRank(sq));
+        }
+        if self.is_in_check(self.side_to_move.opposite()) {
+            return Err(PositionError::OppositeKingInCheck);
+        }
+        Ok(())
     }
     /// Returns an error if the flags provided in a turn are invalid
     pub fn enforce_flags(&self, turn: &Turn) -> Result<(), TurnError> {
+        let mut test_board = self.clone();
         let flags = match turn {
             Turn::Castling(_, flag) => *flag,
             Turn::Move(r#move) => r#move.flags,
+            Turn::Null => flags::NONE,
         };
         if let Turn::Move(r#move) = turn {
             match (
@@ -383,7 +651,7 @@ impl ChessBoard {
             }
         }
         match (
-            self.causes_checkmate(turn),
+            test_board.causes_checkmate(turn),
             is_flag_set(flags, flags::CHECKMATE),
         ) {
             (true, true) => return Ok(()),
@@ -392,7 +660,7 @@ impl ChessBoard {
             (false, false) => (),
         }
         match (
-            self.causes_check(turn, !self.is_white),
+            test_board.causes_check(turn, self.side_to_move.opposite()),
             is_flag_set(flags, flags::CHECK),
         ) {
             (true, true) => (),
@@ -400,21 +668,46 @@ impl ChessBoard {
             (false, true) => return Err(TurnError::RemoveCheckSpecifier),
             (false, false) => (),
         }
-        if self.causes_check(turn, self.is_white) {
+        if test_board.causes_check(turn, self.side_to_move) {
             return Err(TurnError::KingInCheck);
         }
         Ok(())
     }
+    /// Returns an error if a flag provided in a turn is incorrect, while flags that are left out
+    /// are allowed. A check flag is accepted on a checkmating move
+    pub fn validate_given_flags(&self, turn: &Turn) -> Result<(), TurnError> {
+        let flags_of = |turn: &Turn| match turn {
+            Turn::Castling(_, flags) => *flags,
+            Turn::Move(r#move) => r#move.flags,
+            Turn::Null => flags::NONE,
+        };
+        let given = flags_of(turn);
+        let actual = flags_of(&self.gen_flags(*turn));
+        if is_flag_set(given, flags::CAPTURE) && !is_flag_set(actual, flags::CAPTURE) {
+            return Err(TurnError::RemoveCaptureSpecifier);
+        }
+        if is_flag_set(given, flags::CHECKMATE) && !is_flag_set(actual, flags::CHECKMATE) {
+            return Err(TurnError::RemoveCheckmateSpecifier);
+        }
+        if is_flag_set(given, flags::CHECK)
+            && !is_flag_set(actual, flags::CHECK)
+            && !is_flag_set(actual, flags::CHECKMATE)
+        {
+            return Err(TurnError::RemoveCheckSpecifier);
+        }
+        Ok(())
+    }
     /// Returns the inputted turn with the proper flags set
     pub fn gen_flags(&self, turn: Turn) -> Turn {
+        let mut test_board = self.clone();
         let mut flags: u8 = 0;
-        if self.causes_checkmate(&turn) {
+        if test_board.causes_checkmate(&turn) {
             flags |= flags::CHECKMATE;
-        } else if self.causes_check(&turn, !self.is_white) {
+        } else if test_board.causes_check(&turn, self.side_to_move.opposite()) {
             flags |= flags::CHECK;
         }
         if let Turn::Move(Move { dst, .. }) = turn {
-            if self.get(&dst).is_some() || self.en_passant.is_some_and(|sq| sq == dst) {
+            if self.get(&dst).is_some() || self.is_en_passant(&turn) {
                 flags |= flags::CAPTURE;
             }
         };
@@ -422,7 +715,107 @@ impl ChessBoard {
         match turn {
             Turn::Castling(castling_type, _) => Turn::Castling(castling_type, flags),
             Turn::Move(r#move) => Turn::Move(Move { flags, ..r#move }),
+            Turn::Null => Turn::Null,
+        }
+    }
+    /// Returns the zobrist hash of the position, used for detecting repeated positions
+    ///
+    /// Includes the piece locations, current player, castling rights, and the en passant file, but
+    /// only if an en passant capture is possible
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut hash = 0;
+        for (sq, piece) in self.occupied() {
+            hash ^= zobrist::piece_key(*sq, *piece);
+        }
+        if !self.side_to_move.is_white() {
+            hash ^= zobrist::black_to_move_key();
+        }
+        let castling_rights = [
+            self.castling.white_kingside,
+            self.castling.white_queenside,
+            self.castling.black_kingside,
+            self.castling.black_queenside,
+        ];
+        for (i, _) in castling_rights
+            .iter()
+            .enumerate()
+            .filter(|(_, &right)| right)
+        {
+            hash ^= zobrist::castling_key(i);
+        }
+        if let Some(en_passant) = self.capturable_en_passant() {
+            hash ^= zobrist::en_passant_key(en_passant);
+        }
+        hash
+    }
+    /// Returns a key of the position that ignores the half move clock and full move number, so
+    /// the same position reached by different move orders has the same key
+    ///
+    /// Covers the same state as [ChessBoard::zobrist_hash], where the en passant square only
+    /// counts if an en passant capture is possible, but without hash collisions
+    pub fn positional_key(&self) -> PositionKey {
+        PositionKey {
+            piece_locs: self.piece_locs,
+            side_to_move: self.side_to_move,
+            castling: self.castling,
+            en_passant: self.capturable_en_passant(),
+        }
+    }
+    /// Returns the en passant square if a pawn of the current player is able to capture on it
+    fn capturable_en_passant(&self) -> Option<Square> {
+        let en_passant = self.en_passant?;
+        let capturers = if self.side_to_move.is_white() {
+            [en_passant.down_left(), en_passant.down_right()]
+        } else {
+            [en_passant.up_left(), en_passant.up_right()]
+        };
+        let pawn = Piece::new(PieceType::Pawn, self.side_to_move);
+        capturers
+            .iter()
+            .flatten()
+            .any(|sq| self.get(sq) == Some(&pawn))
+            .then_some(en_passant)
+    }
+    /// Returns the board drawn inside a Unicode box, with the ranks labeled on both sides and
+    /// the files labeled above and below, from the given perspective
+    pub fn to_string_bordered(&self, perspective: RotateBoard) -> String {
+        let white_bottom = match perspective {
+            RotateBoard::White => true,
+            RotateBoard::Black => false,
+            RotateBoard::Rotate => self.side_to_move() == Color::White,
+        };
+        let mut ranks: Vec<u8> = (0..8).collect();
+        let mut files: Vec<u8> = (0..8).collect();
+        if white_bottom {
+            ranks.reverse();
+        } else {
+            files.reverse();
+        }
+        let file_labels: String = files
+            .iter()
+            .flat_map(|&file| [' ', (b'a' + file) as char])
+            .collect();
+        let file_labels = format!("  {file_labels}\n");
+
+        let mut output = file_labels.clone();
+        output.push_str(&format!("  ┌{}┐\n", "─".repeat(17)));
+        for &rank in &ranks {
+            let label = rank + 1;
+            output.push_str(&format!("{label} │"));
+            for &file in &files {
+                let sq = Square::from_coords(file, rank).expect("coordinates are on the board");
+                let piece = self.get(&sq).map_or("·".to_string(), |pc| pc.to_string());
+                output.push_str(&format!(" {piece}"));
+            }
+            output.push_str(&format!(" │ {label}\n"));
         }
+        output.push_str(&format!("  └{}┘\n", "─".repeat(17)));
+        output.push_str(&file_labels);
+        output
+    }
+    /// Returns the fen string for the current board state, alias for [ChessBoard::gen_fen]
+    pub fn to_fen(&self) -> String {
+        self.gen_fen()
     }
     /// Returns the fen string for the current board state
     pub fn gen_fen(&self) -> String {
@@ -456,26 +849,13 @@ impl ChessBoard {
         fen.remove(fen.len() - 1);
         fen.push(' ');
 
-        fen.push(if self.is_white { 'w' } else { 'b' });
+        fen.push(match self.side_to_move {
+            Color::White => 'w',
+            Color::Black => 'b',
+        });
         fen.push(' ');
 
-        let mut castling = String::new();
-        if self.castling.white_kingside {
-            castling.push('K');
-        }
-        if self.castling.white_queenside {
-            castling.push('Q');
-        }
-        if self.castling.black_kingside {
-            castling.push('k');
-        }
-        if self.castling.black_queenside {
-            castling.push('q');
-        }
-        if castling.is_empty() {
-            castling.push('-');
-        }
-        fen.push_str(&castling);
+        fen.push_str(&self.castling.to_string());
         fen.push(' ');
 
         if let Some(en_passant) = self.en_passant {
@@ -494,14 +874,16 @@ impl ChessBoard {
     }
     fn validate_move(&self, r#move: &Move) -> Result<Source, TurnError> {
         let mut potential_moves: Vec<(Square, Vec<Square>)> = Vec::new();
+        let mut test_board = self.clone();
         for piece in self.find_pieces(Piece {
             piece: r#move.piece,
-            is_white: self.is_white,
+            color: self.side_to_move,
         }) {
             let mut generated_moves = self.gen_moves(piece);
             if generated_moves.contains(&r#move.dst) {
                 generated_moves.retain(|sq| {
-                    !self.causes_check(&Turn::new((*piece.0, *piece.1), *sq), self.is_white)
+                    !test_board
+                        .causes_check(&Turn::new((*piece.0, *piece.1), *sq), self.side_to_move)
                 });
                 if !generated_moves.is_empty() {
                     potential_moves.push((*piece.0, generated_moves));
@@ -586,35 +968,90 @@ impl ChessBoard {
         Ok(src)
     }
     fn validate_castling(&self, castling: &CastlingType, _flags: &u8) -> Result<(), TurnError> {
-        let is_short = *castling == CastlingType::Short;
-        let castling_squares = match (is_short, self.is_white) {
-            (true, true) => vec![Square::F1, Square::G1],
-            (true, false) => vec![Square::F8, Square::G8],
-            (false, true) => vec![Square::D1, Square::C1, Square::B1],
-            (false, false) => vec![Square::D8, Square::C8, Square::B8],
+        let ((king_src, king_dst), (rook_src, rook_dst)) =
+            self.castling_squares(self.side_to_move, castling);
+        let castling_right = match (castling, self.side_to_move) {
+            (CastlingType::Short, Color::White) => self.castling.white_kingside,
+            (CastlingType::Short, Color::Black) => self.castling.black_kingside,
+            (CastlingType::Long, Color::White) => self.castling.white_queenside,
+            (CastlingType::Long, Color::Black) => self.castling.black_queenside,
         };
-        let castling_right = match (is_short, self.is_white) {
-            (true, true) => self.castling.white_kingside,
-            (true, false) => self.castling.black_kingside,
-            (false, true) => self.castling.white_queenside,
-            (false, false) => self.castling.black_queenside,
+        let rank = king_src.rank_index();
+        let files_between = |a: Square, b: Square| {
+            let (low, high) = if a.file_index() <= b.file_index() {
+                (a.file_index(), b.file_index())
+            } else {
+                (b.file_index(), a.file_index())
+            };
+            (low..=high).map(move |file| Square::from_coords(file, rank).expect("file is valid"))
         };
+        // squares that the king passes through, including where it lands
+        let king_squares: Vec<Square> = files_between(king_src, king_dst).collect();
+        // squares that the king and rook pass through, other than their own
+        let path_blocked = files_between(king_src, king_dst)
+            .chain(files_between(rook_src, rook_dst))
+            .any(|sq| sq != king_src && sq != rook_src && self.get(&sq).is_some());
 
-        if self.get_player_pieces(!self.is_white).any(|full_piece| {
-            let targets = self.gen_targets(full_piece);
-            castling_squares.iter().any(|sq| targets.contains(sq))
-        }) || self.is_in_check(self.is_white)
+        if self
+            .get_player_pieces(self.side_to_move.opposite())
+            .any(|full_piece| {
+                let targets = self.gen_targets(full_piece);
+                king_squares.iter().any(|sq| targets.contains(sq))
+            })
+            || self.is_in_check(self.side_to_move)
         {
             return Err(TurnError::CastleThroughCheck);
         }
-        if castling_squares.iter().any(|sq| self.get(sq).is_some()) {
+        if path_blocked {
             return Err(TurnError::CastlePathBlocked);
         }
         if !castling_right {
             return Err(TurnError::CastleLostRights);
         }
+        // in Chess960 the castling rook can shield the king's destination from a rook or queen
+        // further along the rank, which only attacks the king once the rook has moved
+        let beyond_rook = match rook_src.file_index().cmp(&king_dst.file_index()) {
+            Ordering::Less => 0..rook_src.file_index(),
+            Ordering::Greater => rook_src.file_index() + 1..8,
+            Ordering::Equal => 0..0,
+        };
+        let rook_shields_king = beyond_rook
+            .map(|file| Square::from_coords(file, rank).expect("file is valid"))
+            .filter_map(|sq| self.get(&sq))
+            .any(|piece| {
+                piece.color != self.side_to_move
+                    && matches!(piece.piece, PieceType::Rook | PieceType::Queen)
+            });
+        if rook_shields_king
+            && self
+                .clone()
+                .causes_check(&Turn::Castling(*castling, flags::NONE), self.side_to_move)
+        {
+            return Err(TurnError::CastleThroughCheck);
+        }
         Ok(())
     }
+    /// Returns the start and end squares of the king, then of the rook, when the given player
+    /// castles
+    ///
+    /// The king always lands on the c or g file and the rook on the d or f file, wherever they
+    /// start from in Chess960
+    pub(crate) fn castling_squares(
+        &self,
+        color: Color,
+        castling: &CastlingType,
+    ) -> ((Square, Square), (Square, Square)) {
+        let rank = if color.is_white() { 0 } else { 7 };
+        let square = |file| Square::from_coords(file, rank).expect("file is valid");
+        let (rook_file, king_dst, rook_dst) = match castling {
+            CastlingType::Short => (self.castling.kingside_rook_file, 6, 5),
+            CastlingType::Long => (self.castling.queenside_rook_file, 2, 3),
+        };
+        (
+            (square(self.castling.king_file), square(king_dst)),
+            (square(rook_file), square(rook_dst)),
+        )
+    }
     /// Returns the turn with the least amount of information to fully specify a move, given a
     /// fully qualified move.
     ///
@@ -623,7 +1060,7 @@ impl ChessBoard {
     /// Panics if the input move does not have a [Source::Square] as the source.
     pub fn get_minimum_move(&self, turn: &Turn) -> Turn {
         match turn {
-            Turn::Castling(_, _) => *turn,
+            Turn::Castling(_, _) | Turn::Null => *turn,
             Turn::Move(r#move) => {
                 let file = match r#move.src {
                     Some(Source::Square(sq)) => sq.file(),
@@ -659,51 +1096,344 @@ impl ChessBoard {
             }
         }
     }
+    /// Returns the standard algebraic notation of a fully qualified turn, with the minimum
+    /// disambiguation and the capture, promotion, check, and checkmate markers
+    ///
+    /// # Panics
+    ///
+    /// Panics if the input move does not have a [Source::Square] as the source.
+    pub fn to_san(&self, turn: &Turn) -> String {
+        let flagged = self.gen_flags(*turn);
+        let flags = match flagged {
+            Turn::Castling(_, flags) => flags,
+            Turn::Move(r#move) => r#move.flags,
+            Turn::Null => flags::NONE,
+        };
+        let suffix = if is_flag_set(flags, flags::CHECKMATE) {
+            "#"
+        } else if is_flag_set(flags, flags::CHECK) {
+            "+"
+        } else {
+            ""
+        };
+        match self.get_minimum_move(&flagged) {
+            Turn::Castling(CastlingType::Short, _) => format!("O-O{suffix}"),
+            Turn::Castling(CastlingType::Long, _) => format!("O-O-O{suffix}"),
+            Turn::Null => Turn::Null.to_string(),
+            Turn::Move(mut r#move) => {
+                // pawn captures are always written with the file of the pawn
+                if r#move.piece == PieceType::Pawn && is_flag_set(flags, flags::CAPTURE) {
+                    let Turn::Move(Move {
+                        src: Some(Source::Square(src)),
+                        ..
+                    }) = turn
+                    else {
+                        panic!("No specified source");
+                    };
+                    r#move.src = Some(Source::Line(src.file()));
+                }
+                Turn::Move(r#move).to_string()
+            }
+        }
+    }
+    /// Returns the standard algebraic notation of a fully qualified turn, as in
+    /// [ChessBoard::to_san], with the letters of the moving and promoted pieces replaced by the
+    /// Unicode symbols of the current player's pieces, such as `♘f3`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the input move does not have a [Source::Square] as the source.
+    pub fn to_san_figurine(&self, turn: &Turn) -> String {
+        let mut san = self.to_san(turn);
+        let Turn::Move(r#move) = turn else {
+            return san;
+        };
+        let symbol = |piece| Piece::new(piece, self.side_to_move).to_string();
+        if r#move.piece != PieceType::Pawn {
+            san.replace_range(..1, &symbol(r#move.piece));
+        }
+        if let (Some(promotion), Some(index)) = (r#move.promotion, san.find('=')) {
+            san.replace_range(index + 1..index + 2, &symbol(promotion));
+        }
+        san
+    }
     fn find_pieces(&self, piece: Piece) -> impl Iterator<Item = (&Square, &Piece)> {
-        self.piece_locs
-            .iter()
-            .filter(move |&(_, pc)| pc.piece == piece.piece && pc.is_white == piece.is_white)
+        self.occupied()
+            .filter(move |&(_, pc)| pc.piece == piece.piece && pc.color == piece.color)
     }
-    fn is_in_check(&self, is_white: bool) -> bool {
-        let mut king = self.find_pieces(Piece {
-            piece: PieceType::King,
-            is_white,
-        });
-        if let Some(king) = king.next() {
-            self.get_player_pieces(!is_white)
-                .any(|full_piece| self.gen_targets(full_piece).contains(king.0))
+    /// Returns the square of the king of the given player, returns `None` if the board has no
+    /// king of that color
+    pub fn find_king(&self, color: impl Into<Color>) -> Option<Square> {
+        self.find_pieces(Piece::new(PieceType::King, color))
+            .next()
+            .map(|(&sq, _)| sq)
+    }
+    /// Returns whether the king of the given player is in check
+    pub fn is_in_check(&self, color: impl Into<Color>) -> bool {
+        let color = color.into();
+        if let Some(king) = self.find_king(color) {
+            self.get_player_pieces(color.opposite())
+                .any(|full_piece| self.gen_targets(full_piece).contains(&king))
         } else {
             false
         }
     }
+    /// Returns whether the current player is checkmated, having no legal moves while in check
+    pub fn is_checkmate(&self) -> bool {
+        self.is_in_check(self.side_to_move) && self.legal_moves().is_empty()
+    }
+    /// Returns whether the current player is stalemated, having no legal moves while not in
+    /// check
+    pub fn is_stalemate(&self) -> bool {
+        !self.is_in_check(self.side_to_move) && self.legal_moves().is_empty()
+    }
+    /// Returns whether the turn is a pawn capturing en passant, moving diagonally onto the empty
+    /// en passant square
+    pub fn is_en_passant(&self, turn: &Turn) -> bool {
+        let Turn::Move(r#move) = turn else {
+            return false;
+        };
+        let diagonal = match r#move.src {
+            Some(Source::Square(src)) => src.file() != r#move.dst.file(),
+            Some(Source::Line(line)) => line != r#move.dst.file(),
+            None => true,
+        };
+        r#move.piece == PieceType::Pawn
+            && diagonal
+            && self.en_passant == Some(r#move.dst)
+            && self.get(&r#move.dst).is_none()
+    }
+    /// Returns whether the turn is a legal pawn move onto the last rank that is missing its
+    /// promotion piece, so an interface knows to ask the player which piece to promote to
+    pub fn needs_promotion(&self, turn: &Turn) -> bool {
+        let Turn::Move(r#move) = turn else {
+            return false;
+        };
+        let last_rank = match self.side_to_move {
+            Color::White => Line::Rank8,
+            Color::Black => Line::Rank1,
+        };
+        r#move.piece == PieceType::Pawn
+            && r#move.promotion.is_none()
+            && r#move.dst.rank() == last_rank
+            && self
+                .validate_and_complete_turn(turn.with_promotion(PieceType::Queen))
+                .is_ok()
+    }
+    /// Returns whether the turn puts the opponent in check, validating and completing the turn
+    /// first so a minimal move can be passed
+    ///
+    /// Returns `false` if the turn is illegal
+    pub fn gives_check(&self, turn: &Turn) -> bool {
+        match self.validate_and_complete_turn(*turn) {
+            Ok(turn) => self
+                .clone()
+                .causes_check(&turn, self.side_to_move.opposite()),
+            Err(_) => false,
+        }
+    }
+    /// Returns whether the turn checkmates the opponent, validating and completing the turn first
+    /// so a minimal move can be passed
+    ///
+    /// Returns `false` if the turn is illegal
+    pub fn gives_checkmate(&self, turn: &Turn) -> bool {
+        match self.validate_and_complete_turn(*turn) {
+            Ok(turn) => self.clone().causes_checkmate(&turn),
+            Err(_) => false,
+        }
+    }
+    /// Returns every legal turn for the current player that delivers checkmate
+    ///
+    /// Each returned move has a fully qualified [Source::Square] as the source
+    pub fn mate_in_one(&self) -> Vec<Turn> {
+        let mut test_board = self.clone();
+        self.legal_moves()
+            .into_iter()
+            .filter(|turn| test_board.causes_checkmate(turn))
+            .collect()
+    }
+    /// Returns the squares of every piece of the given player that attacks the provided square
+    pub fn attackers_of(&self, sq: Square, by: impl Into<Color>) -> Vec<Square> {
+        self.get_player_pieces(by.into())
+            .filter(|&full_piece| self.gen_targets(full_piece).contains(&sq))
+            .map(|(loc, _)| *loc)
+            .collect()
+    }
+    /// Returns every square attacked by the given player, whether empty or occupied
+    #[cfg(feature = "std")]
+    pub fn attacked_squares(&self, by: impl Into<Color>) -> std::collections::HashSet<Square> {
+        self.get_player_pieces(by.into())
+            .flat_map(|full_piece| self.gen_targets(full_piece))
+            .collect()
+    }
+    /// Returns every piece of the given player that is absolutely pinned to its king, as the
+    /// square of the pinned piece and the square of the enemy piece pinning it
+    pub fn pinned_pieces(&self, color: impl Into<Color>) -> Vec<(Square, Square)> {
+        let color = color.into();
+        let Some(king) = self.find_king(color) else {
+            return Vec::new();
+        };
+        let attackers = self.attackers_of(king, color.opposite());
+        let mut test_board = self.clone();
+        let mut pins = Vec::new();
+        for (&sq, &piece) in self.get_player_pieces(color) {
+            if piece.piece == PieceType::King {
+                continue;
+            }
+            test_board.remove(&sq);
+            pins.extend(
+                test_board
+                    .attackers_of(king, color.opposite())
+                    .into_iter()
+                    .filter(|attacker| !attackers.contains(attacker))
+                    .map(|attacker| (sq, attacker)),
+            );
+            test_board.insert((sq, piece));
+        }
+        pins
+    }
     /// Returns the piece at the provided square, returns `None` if there is no piece at that
     /// square
     pub fn get(&self, sq: &Square) -> Option<&Piece> {
-        self.piece_locs.get(sq)
+        self.piece_locs[sq.to_index()].as_ref()
     }
-    fn insert(&mut self, piece: (Square, Piece)) {
-        self.piece_locs.insert(piece.0, piece.1);
+    /// Returns the piece at the provided square by value, returns `None` if there is no piece at
+    /// that square
+    pub fn piece_at(&self, sq: Square) -> Option<Piece> {
+        self.piece_locs[sq.to_index()]
     }
-    fn remove(&mut self, sq: &Square) {
-        self.piece_locs.remove(sq);
+    /// Returns an iterator over every occupied square and the piece on it, ordered from a8 to h1
+    pub fn pieces(&self) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        self.occupied().map(|(sq, piece)| (*sq, *piece))
     }
-    fn gen_moves(&self, full_piece: (&Square, &Piece)) -> Vec<Square> {
-        let (loc, piece) = full_piece;
-        let mut moves: Vec<_> = self
-            .gen_targets(full_piece)
-            .into_iter()
-            .filter(|sq| {
-                self.get(sq).is_none()
-                    || self.get(sq).expect("is some from previous check").is_white != self.is_white
-            })
-            .collect();
-        if piece.piece == PieceType::Pawn {
-            moves.retain(|sq| {
-                self.get(sq).is_some_and(|pc| pc.is_white != self.is_white)
+    /// Returns the sum of the values of the given player's pieces, see [PieceType::value]
+    pub fn material(&self, color: impl Into<Color>) -> i32 {
+        self.get_player_pieces(color.into())
+            .map(|(_, piece)| piece.piece.value())
+            .sum()
+    }
+    /// Returns the material of the white player minus the material of the black player
+    pub fn material_balance(&self) -> i32 {
+        self.material(Color::White) - self.material(Color::Black)
+    }
+    /// Places the piece on the provided square, replacing any piece that is already there
+    ///
+    /// # Side effects
+    ///
+    /// Removes castling and en passant rights that are no longer possible in the new position
+    pub fn set_piece(&mut self, sq: Square, piece: Piece) {
+        self.insert((sq, piece));
+        self.revoke_invalid_rights();
+    }
+    /// Removes the piece on the provided square, returning it if there was one
+    ///
+    /// # Side effects
+    ///
+    /// Removes castling and en passant rights that are no longer possible in the new position
+    pub fn remove_piece(&mut self, sq: Square) -> Option<Piece> {
+        let piece = self.piece_locs[sq.to_index()].take();
+        self.revoke_invalid_rights();
+        piece
+    }
+    /// Removes every piece from the board, along with the castling and en passant rights
+    pub fn clear(&mut self) {
+        self.piece_locs = [None; 64];
+        self.revoke_invalid_rights();
+    }
+    /// Sets the player whose turn it is
+    ///
+    /// # Side effects
+    ///
+    /// Removes the en passant rights, since they belong to the player that was to move
+    pub fn set_side_to_move(&mut self, color: Color) {
+        if self.side_to_move != color {
+            self.side_to_move = color;
+            self.en_passant = None;
+        }
+    }
+    /// Returns the position flipped across the horizontal midline with the colors swapped, so
+    /// that the other player has the identical position
+    pub fn mirror(&self) -> ChessBoard {
+        let mirror_square = |sq: Square| {
+            Square::from_coords(sq.file_index(), 7 - sq.rank_index()).expect("is valid square")
+        };
+        let mut piece_locs = [None; 64];
+        for (sq, piece) in self.pieces() {
+            piece_locs[mirror_square(sq).to_index()] =
+                Some(Piece::new(piece.piece, piece.color.opposite()));
+        }
+        ChessBoard {
+            piece_locs,
+            side_to_move: self.side_to_move.opposite(),
+            castling: CastlingRights {
+                white_kingside: self.castling.black_kingside,
+                white_queenside: self.castling.black_queenside,
+                black_kingside: self.castling.white_kingside,
+                black_queenside: self.castling.white_queenside,
+                ..self.castling
+            },
+            en_passant: self.en_passant.map(mirror_square),
+            half_move_clock: self.half_move_clock,
+            full_move_number: self.full_move_number,
+        }
+    }
+    fn revoke_invalid_rights(&mut self) {
+        let has = |file: u8, piece: PieceType, color: Color| {
+            let rank = if color.is_white() { 0 } else { 7 };
+            let sq = Square::from_coords(file, rank).expect("file is valid");
+            self.get(&sq) == Some(&Piece::new(piece, color))
+        };
+        let CastlingRights {
+            king_file,
+            kingside_rook_file,
+            queenside_rook_file,
+            ..
+        } = self.castling;
+        let white_king = has(king_file, PieceType::King, Color::White);
+        let black_king = has(king_file, PieceType::King, Color::Black);
+        let rights = [
+            white_king && has(kingside_rook_file, PieceType::Rook, Color::White),
+            white_king && has(queenside_rook_file, PieceType::Rook, Color::White),
+            black_king && has(kingside_rook_file, PieceType::Rook, Color::Black),
+            black_king && has(queenside_rook_file, PieceType::Rook, Color::Black),
+        ];
+        self.castling.white_kingside &= rights[0];
+        self.castling.white_queenside &= rights[1];
+        self.castling.black_kingside &= rights[2];
+        self.castling.black_queenside &= rights[3];
+        // the pawn that moved two squares must still be in front of the en passant square
+        let en_passant_pawn = match self.side_to_move {
+            Color::White => self.en_passant.and_then(|sq| sq.down()),
+            Color::Black => self.en_passant.and_then(|sq| sq.up()),
+        };
+        let pawn = Piece::new(PieceType::Pawn, self.side_to_move.opposite());
+        if en_passant_pawn.is_some_and(|sq| self.get(&sq) != Some(&pawn)) {
+            self.en_passant = None;
+        }
+    }
+    fn insert(&mut self, piece: (Square, Piece)) {
+        self.piece_locs[piece.0.to_index()] = Some(piece.1);
+    }
+    fn remove(&mut self, sq: &Square) {
+        self.piece_locs[sq.to_index()] = None;
+    }
+    fn gen_moves(&self, full_piece: (&Square, &Piece)) -> Vec<Square> {
+        let (loc, piece) = full_piece;
+        let mut moves: Vec<_> = self
+            .gen_targets(full_piece)
+            .into_iter()
+            .filter(|sq| {
+                self.get(sq).is_none()
+                    || self.get(sq).expect("is some from previous check").color != self.side_to_move
+            })
+            .collect();
+        if piece.piece == PieceType::Pawn {
+            moves.retain(|sq| {
+                self.get(sq).is_some_and(|pc| pc.color != self.side_to_move)
                     || self.en_passant.is_some_and(|a| a == *sq)
             })
         }
-        if piece.piece == PieceType::Pawn && piece.is_white {
+        if piece.piece == PieceType::Pawn && piece.color == Color::White {
             let uu = |sq: &Square| sq.up()?.up();
             if let Some(next_sq) = loc.up() {
                 if self.get(&next_sq).is_none() {
@@ -720,7 +1450,7 @@ impl ChessBoard {
                     moves.push(next_sq);
                 }
             }
-        } else if piece.piece == PieceType::Pawn && !piece.is_white {
+        } else if piece.piece == PieceType::Pawn && piece.color == Color::Black {
             let dd = |sq: &Square| sq.down()?.down();
             if let Some(next_sq) = loc.down() {
                 if self.get(&next_sq).is_none() {
@@ -755,23 +1485,7 @@ impl ChessBoard {
             }
         };
         match piece.piece {
-            PieceType::King => {
-                let directions = vec![
-                    Square::up,
-                    Square::down,
-                    Square::right,
-                    Square::left,
-                    Square::up_right,
-                    Square::up_left,
-                    Square::down_right,
-                    Square::down_left,
-                ];
-                for direction in directions {
-                    if let Some(next_sq) = direction(loc) {
-                        moves.push(next_sq);
-                    }
-                }
-            }
+            PieceType::King => moves.extend(attacks::king_attacks(*loc)),
             PieceType::Queen => {
                 let directions = vec![
                     Square::up,
@@ -819,24 +1533,9 @@ impl ChessBoard {
                     }
                 }
             }
-            PieceType::Knight => {
-                let uur = |sq: &Square| sq.up()?.up()?.right();
-                let uul = |sq: &Square| sq.up()?.up()?.left();
-                let rru = |sq: &Square| sq.right()?.right()?.up();
-                let rrd = |sq: &Square| sq.right()?.right()?.down();
-                let ddr = |sq: &Square| sq.down()?.down()?.right();
-                let ddl = |sq: &Square| sq.down()?.down()?.left();
-                let llu = |sq: &Square| sq.left()?.left()?.up();
-                let lld = |sq: &Square| sq.left()?.left()?.down();
-                let directions = vec![uur, uul, rru, rrd, ddr, ddl, llu, lld];
-                for direction in directions {
-                    if let Some(sq) = direction(loc) {
-                        moves.push(sq);
-                    }
-                }
-            }
-            PieceType::Pawn => match piece.is_white {
-                true => {
+            PieceType::Knight => moves.extend(attacks::knight_attacks(*loc)),
+            PieceType::Pawn => match piece.color {
+                Color::White => {
                     if let Some(sq) = loc.up_right() {
                         moves.push(sq);
                     }
@@ -844,7 +1543,7 @@ impl ChessBoard {
                         moves.push(sq);
                     }
                 }
-                false => {
+                Color::Black => {
                     if let Some(sq) = loc.down_right() {
                         moves.push(sq);
                     }
@@ -856,63 +1555,83 @@ impl ChessBoard {
         }
         moves
     }
-    fn causes_check(&self, turn: &Turn, is_white: bool) -> bool {
-        let mut test_board = self.clone();
-        test_board.update_board(turn);
-        test_board.is_in_check(is_white)
+    /// Returns whether the player of the given color is in check after the turn, leaving the
+    /// board unchanged
+    fn causes_check(&mut self, turn: &Turn, color: Color) -> bool {
+        let token = self.make_unchecked(turn);
+        let in_check = self.is_in_check(color);
+        self.unmake(token);
+        in_check
     }
-    fn causes_checkmate(&self, turn: &Turn) -> bool {
-        let mut test_board = self.clone();
-        test_board.update_board(turn);
-        matches!(
-            test_board.check_gamestate(&Counter::new()),
-            GameState::Win(_)
-        )
+    /// Returns whether the turn checkmates the opponent, leaving the board unchanged
+    fn causes_checkmate(&mut self, turn: &Turn) -> bool {
+        let token = self.make_unchecked(turn);
+        let is_checkmate = matches!(self.check_gamestate(&Counter::new()), GameState::Win(_));
+        self.unmake(token);
+        is_checkmate
     }
-    fn get_player_pieces(&self, is_white: bool) -> impl Iterator<Item = (&Square, &Piece)> {
-        self.piece_locs
+    /// Returns an iterator over the occupied squares and their pieces, from a8 to h1
+    fn occupied(&self) -> impl Iterator<Item = (&Square, &Piece)> {
+        Square::ALL
             .iter()
-            .filter(move |(_, pc)| pc.is_white == is_white)
+            .zip(&self.piece_locs)
+            .filter_map(|(sq, piece)| Some((sq, piece.as_ref()?)))
+    }
+    fn get_player_pieces(&self, color: Color) -> impl Iterator<Item = (&Square, &Piece)> {
+        self.occupied().filter(move |(_, pc)| pc.color == color)
     }
+    // Positions where neither side can checkmate by any sequence of legal moves
+    //
+    // | White       | Black       | Dead  |
+    // |-------------|-------------|-------|
+    // | K           | K           | true  |
+    // | K + B/N     | K           | true  |
+    // | K + B...    | K + B...    | true if all bishops are on the same color |
+    // | K + N + N   | K           | false |
+    // | K + B/N     | K + N       | false |
+    // | any P/R/Q   | any         | false |
     fn is_insufficient_material(&self) -> bool {
-        let white_pieces: Vec<_> = self.get_player_pieces(true).collect();
-        let black_pieces: Vec<_> = self.get_player_pieces(false).collect();
-        white_pieces.len() == 1 && black_pieces.len() == 1
-            || black_pieces.len() == 1
-                && white_pieces.len() == 2
-                && white_pieces
-                    .iter()
-                    .filter(|(_, pc)| [PieceType::Bishop, PieceType::Knight].contains(&pc.piece))
-                    .count()
-                    == 1
-            || white_pieces.len() == 1
-                && black_pieces.len() == 2
-                && black_pieces
-                    .iter()
-                    .filter(|(_, pc)| [PieceType::Bishop, PieceType::Knight].contains(&pc.piece))
-                    .count()
-                    == 1
-            || white_pieces.len() == 2
-                && black_pieces.len() == 2
-                && white_pieces
-                    .iter()
-                    .find(|(_, pc)| pc.piece == PieceType::Bishop)
-                    .is_some_and(|(white_loc, _)| {
-                        black_pieces
-                            .iter()
-                            .find(|(_, pc)| pc.piece == PieceType::Bishop)
-                            .is_some_and(|(black_loc, _)| {
-                                black_loc.is_light() == white_loc.is_light()
-                            })
-                    })
+        let non_king_pieces: Vec<_> = self
+            .occupied()
+            .filter(|(_, pc)| pc.piece != PieceType::King)
+            .collect();
+        if non_king_pieces.iter().any(|(_, pc)| {
+            matches!(
+                pc.piece,
+                PieceType::Pawn | PieceType::Rook | PieceType::Queen
+            )
+        }) {
+            return false;
+        }
+        if non_king_pieces.len() <= 1 {
+            return true;
+        }
+        let mut bishops = non_king_pieces.iter().map(|(loc, pc)| {
+            if pc.piece == PieceType::Bishop {
+                Some(loc.is_light())
+            } else {
+                None
+            }
+        });
+        let first_color = bishops.next().flatten();
+        first_color.is_some() && bishops.all(|color| color == first_color)
     }
-    fn is_threefold_repitition(&self, position_hist: &Counter<String>) -> bool {
-        position_hist.counts().any(|&count| count >= 3)
+    /// Returns whether any position in the history has occurred at least three times
+    pub fn is_threefold_repetition(&self, position_hist: &Counter<u64>) -> bool {
+        position_hist.max_count() >= 3
+    }
+    /// Returns whether any position in the history has occurred at least three times
+    #[deprecated(note = "use `ChessBoard::is_threefold_repetition` instead")]
+    pub fn is_threefold_repitition(&self, position_hist: &Counter<u64>) -> bool {
+        self.is_threefold_repetition(position_hist)
+    }
+    fn repetition_count(&self, position_hist: &Counter<u64>) -> usize {
+        position_hist.get(&self.zobrist_hash()) + 1
     }
 }
 
 impl Display for ChessBoard {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let mut output = String::new();
         if f.alternate() {
             for (i, rank) in ('1'..='8').filter_map(Line::new).enumerate() {
@@ -948,7 +1667,8 @@ impl FromStr for ChessBoard {
     ///
     /// # Errors
     ///
-    /// returns an error if the given FEN string is an invalid format
+    /// returns an error if the given FEN string is an invalid format, or if the position is
+    /// illegal as described by [ChessBoard::validate_position]
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut fen_split = s.split_whitespace();
         let board = fen_split
@@ -973,13 +1693,21 @@ impl FromStr for ChessBoard {
             return Err("Additional fields specified");
         };
 
-        let mut piece_locs: HashMap<Square, Piece> = HashMap::new();
+        let mut piece_locs = [None; 64];
         let mut board_squares = Square::iterator();
+        if board.split('/').count() != 8 {
+            return Err("Invalid number of ranks on the board");
+        }
         for rank in board.split('/') {
             let mut count = 0;
+            let mut last_was_empty = false;
 
             for char in rank.chars() {
                 if let Some(num_empty) = char.to_digit(10) {
+                    if num_empty == 0 || last_was_empty {
+                        return Err("Invalid empty square count on a line");
+                    }
+                    last_was_empty = true;
                     count += num_empty;
                     for _ in 0..num_empty {
                         board_squares.next();
@@ -989,76 +1717,29 @@ impl FromStr for ChessBoard {
                 let sq = board_squares
                     .next()
                     .ok_or("Too many locations on the board")?;
+                last_was_empty = false;
                 count += 1;
                 let piece = Piece {
                     piece: char
                         .to_ascii_uppercase()
                         .try_into()
                         .map_err(|_| "Invalid character in board")?,
-                    is_white: char.is_ascii_uppercase(),
+                    color: char.is_ascii_uppercase().into(),
                 };
-                piece_locs.insert(sq, piece);
+                piece_locs[sq.to_index()] = Some(piece);
             }
             if count != 8 {
                 return Err("Invalid number of pieces on a line");
             }
         }
 
-        let is_white = match player {
-            "w" => true,
-            "b" => false,
+        let side_to_move = match player {
+            "w" => Color::White,
+            "b" => Color::Black,
             _ => return Err("Invalid player specified"),
         };
 
-        let mut castling = CastlingRights {
-            white_kingside: false,
-            white_queenside: false,
-            black_kingside: false,
-            black_queenside: false,
-        };
-        let mut castling_chars = castling_rights.chars();
-        let mut full_castling = vec!['K', 'Q', 'k', 'q'].into_iter();
-        let mut passed_chars = Vec::new();
-
-        let mut next_input_char = castling_chars.next();
-        let mut next_test_char = full_castling.next();
-        loop {
-            let current_target = match next_test_char {
-                Some('K') => &mut castling.white_kingside,
-                Some('Q') => &mut castling.white_queenside,
-                Some('k') => &mut castling.black_kingside,
-                Some('q') => &mut castling.black_queenside,
-                None if next_input_char.is_none() => break,
-                None if next_input_char.is_some() => {
-                    return Err("Additional characters specified after `q` which is the end")
-                }
-                _ => unreachable!(),
-            };
-
-            if next_test_char == next_input_char {
-                *current_target = true;
-                passed_chars.push(next_test_char);
-                next_input_char = castling_chars.next();
-                next_test_char = full_castling.next();
-            } else if next_input_char.is_none() {
-                break;
-            } else if next_input_char == Some('-') {
-                if castling_chars.next().is_some() {
-                    return Err("Additional characters specified after `-`");
-                }
-                break;
-            } else if !matches!(
-                next_input_char,
-                Some('K') | Some('Q') | Some('k') | Some('q')
-            ) {
-                return Err("Invalid characters in castling input");
-            } else if passed_chars.contains(&next_input_char) {
-                return Err("Out of order castling");
-            } else {
-                passed_chars.push(next_test_char);
-                next_test_char = full_castling.next();
-            }
-        }
+        let castling = CastlingRights::from_fen(castling_rights, &piece_locs)?;
 
         let en_passant = match en_passant {
             "-" => None,
@@ -1068,6 +1749,13 @@ impl FromStr for ChessBoard {
             ),
             _ => return Err("En passant was not a square"),
         };
+        let en_passant_rank = match side_to_move {
+            Color::White => Line::Rank6,
+            Color::Black => Line::Rank3,
+        };
+        if en_passant.is_some_and(|sq| sq.rank() != en_passant_rank) {
+            return Err("En passant square is on the wrong rank");
+        }
 
         let half_move_clock = half_move_clock
             .parse::<u8>()
@@ -1076,14 +1764,33 @@ impl FromStr for ChessBoard {
             .parse::<u16>()
             .map_err(|_| "Full move number was not a number")?;
 
-        Ok(ChessBoard {
+        let board = ChessBoard {
             piece_locs,
-            is_white,
+            side_to_move,
             castling,
             en_passant,
             half_move_clock,
             full_move_number,
-        })
+        };
+        board.validate_position().map_err(|e| e.reason())?;
+        Ok(board)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ChessBoard {
+    /// serializes the board as its FEN string
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.gen_fen())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ChessBoard {
+    /// deserializes the board from a FEN string
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fen = String::deserialize(deserializer)?;
+        fen.parse().map_err(serde::de::Error::custom)
     }
 }
 
@@ -1092,6 +1799,7 @@ fn is_flag_set(flags: u8, check_flag: u8) -> bool {
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The current game state
 pub enum GameState {
     #[default]
@@ -1105,7 +1813,75 @@ pub enum GameState {
     Stop,
 }
 
+impl Display for GameState {
+    /// Describes the state with the players named White and Black, such as
+    /// `White wins by checkmate`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.outcome() {
+            Some(outcome) => write!(f, "{outcome}"),
+            None => write!(f, "The game is in progress"),
+        }
+    }
+}
+
+impl GameState {
+    /// Returns the outcome of the game, or `None` if the game is still in play
+    pub fn outcome(&self) -> Option<Outcome> {
+        match self {
+            GameState::Continue => None,
+            GameState::Win(Win {
+                is_white: true,
+                kind,
+            }) => Some(Outcome::WhiteWins(*kind)),
+            GameState::Win(Win {
+                is_white: false,
+                kind,
+            }) => Some(Outcome::BlackWins(*kind)),
+            GameState::Draw(draw) => Some(Outcome::Draw(*draw)),
+            GameState::Stop => Some(Outcome::Aborted),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The result of a finished game
+pub enum Outcome {
+    /// White won the game
+    WhiteWins(WinType),
+    /// Black won the game
+    BlackWins(WinType),
+    /// The game was drawn
+    Draw(DrawType),
+    /// The game was stopped without a result
+    Aborted,
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::WhiteWins(kind) => write!(f, "White wins by {kind}"),
+            Outcome::BlackWins(kind) => write!(f, "Black wins by {kind}"),
+            Outcome::Draw(draw) => write!(f, "The game is a draw by {draw}"),
+            Outcome::Aborted => write!(f, "The game was aborted"),
+        }
+    }
+}
+
+impl Outcome {
+    /// Returns the game termination marker used in pgn files, `*` if the game was aborted
+    pub fn pgn_result_token(&self) -> &'static str {
+        match self {
+            Outcome::WhiteWins(_) => "1-0",
+            Outcome::BlackWins(_) => "0-1",
+            Outcome::Draw(_) => "1/2-1/2",
+            Outcome::Aborted => "*",
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The information describing the win state
 pub struct Win {
     /// The player who won, true if white
@@ -1114,6 +1890,7 @@ pub struct Win {
     pub kind: WinType,
 }
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The type of win
 pub enum WinType {
     /// Win by checkmate
@@ -1123,28 +1900,243 @@ pub enum WinType {
     /// Win by timeout
     Timeout,
 }
+impl Display for WinType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            WinType::Checkmate => "checkmate",
+            WinType::Resign => "resignation",
+            WinType::Timeout => "timeout",
+        };
+        write!(f, "{kind}")
+    }
+}
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The type of draw
 pub enum DrawType {
     /// Draw by stalemate
     Stalemate,
-    /// Draw by the fifty move rule
+    /// Draw by the fifty move rule, claimed by a player
     FiftyMove,
-    /// Draw by threefold repetition
-    ThreefoldRepitition,
+    /// Draw by the seventy-five move rule
+    SeventyFiveMove,
+    /// Draw by threefold repetition, claimed by a player
+    #[cfg_attr(feature = "serde", serde(alias = "ThreefoldRepitition"))]
+    ThreefoldRepetition,
+    /// Draw by fivefold repetition
+    FivefoldRepetition,
     /// Draw by insufficient material
     InsufficientMaterial,
-    /// Draw by draw offer
-    Offer,
+    /// Draw by agreement, after one player accepts the other's draw offer
+    #[cfg_attr(feature = "serde", serde(alias = "Offer"))]
+    Agreement,
+}
+impl DrawType {
+    /// The previous, misspelled name of [DrawType::ThreefoldRepetition]
+    #[deprecated(note = "renamed to `DrawType::ThreefoldRepetition`")]
+    #[allow(non_upper_case_globals)]
+    pub const ThreefoldRepitition: DrawType = DrawType::ThreefoldRepetition;
+    /// The previous name of [DrawType::Agreement]
+    #[deprecated(note = "renamed to `DrawType::Agreement`")]
+    #[allow(non_upper_case_globals)]
+    pub const Offer: DrawType = DrawType::Agreement;
+}
+impl Display for DrawType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            DrawType::Stalemate => "stalemate",
+            DrawType::FiftyMove => "the fifty move rule",
+            DrawType::SeventyFiveMove => "the seventy-five move rule",
+            DrawType::ThreefoldRepetition => "threefold repetition",
+            DrawType::FivefoldRepetition => "fivefold repetition",
+            DrawType::InsufficientMaterial => "insufficient material",
+            DrawType::Agreement => "agreement",
+        };
+        write!(f, "{kind}")
+    }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-struct CastlingRights {
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[must_use]
+/// The turn and the board state that is lost when it is made, returned by
+/// [ChessBoard::make_unchecked] and used by [ChessBoard::unmake] to undo the turn
+pub struct UndoToken {
+    turn: Turn,
+    moved_piece: Option<Piece>,
+    captured: Option<(Square, Piece)>,
+    castling: CastlingRights,
+    en_passant: Option<Square>,
+    half_move_clock: u8,
+}
+
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, PartialEq, Debug)]
+/// The undo token of a turn made in a game, along with the position before the turn
+pub(crate) struct UndoRecord {
+    pub(crate) token: UndoToken,
+    /// The zobrist hash of the position before the turn
+    pub(crate) position_hash: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+/// Whether each player keeps the right to castle on each side
+///
+/// A right is lost once the king or the corresponding rook moves, even if castling is currently
+/// blocked. Also stores the files that the king and rooks start on, which are only different from
+/// the standard e, h, and a files in Chess960
+pub struct CastlingRights {
     white_kingside: bool,
     white_queenside: bool,
     black_kingside: bool,
     black_queenside: bool,
+    king_file: u8,
+    kingside_rook_file: u8,
+    queenside_rook_file: u8,
+}
+impl CastlingRights {
+    /// Returns the zero-indexed file that the kings start on
+    pub fn king_file(&self) -> u8 {
+        self.king_file
+    }
+    /// Returns the zero-indexed file that the kingside rooks start on
+    pub fn kingside_rook_file(&self) -> u8 {
+        self.kingside_rook_file
+    }
+    /// Returns the zero-indexed file that the queenside rooks start on
+    pub fn queenside_rook_file(&self) -> u8 {
+        self.queenside_rook_file
+    }
+    /// Removes the rights that depend on a piece staying on the square, which is the king's
+    /// square only if the piece is leaving it
+    fn revoke_for_square(&mut self, sq: Square, leaving: bool) {
+        let is_white = match sq.rank_index() {
+            0 => true,
+            7 => false,
+            _ => return,
+        };
+        let file = sq.file_index();
+        let king = leaving && file == self.king_file;
+        let (kingside, queenside) = if is_white {
+            (&mut self.white_kingside, &mut self.white_queenside)
+        } else {
+            (&mut self.black_kingside, &mut self.black_queenside)
+        };
+        if king || file == self.kingside_rook_file {
+            *kingside = false;
+        }
+        if king || file == self.queenside_rook_file {
+            *queenside = false;
+        }
+    }
+    /// Parses the castling field of a FEN string, given the pieces on the board
+    ///
+    /// Accepts `KQkq`, as well as the rook files used by Shredder-FEN, such as `HAha`. With the
+    /// king on the e-file, `K` and `Q` refer to the h and a files as in standard chess, otherwise
+    /// they refer to the outermost rook on that side of the king, as in X-FEN. A Chess960 position
+    /// with the king on the e-file and an inner castling rook needs the rook's file instead
+    fn from_fen(field: &str, piece_locs: &[Option<Piece>; 64]) -> Result<Self, &'static str> {
+        let mut castling = CastlingRights {
+            white_kingside: false,
+            white_queenside: false,
+            black_kingside: false,
+            black_queenside: false,
+            ..CastlingRights::default()
+        };
+        if field == "-" {
+            return Ok(castling);
+        }
+        // king, kingside rook, and queenside rook files, which must match for both players
+        let mut files: [Option<u8>; 3] = [None; 3];
+        let mut set_file = |i: usize, file: u8| match files[i] {
+            Some(prev) if prev != file => Err("Castling files differ between the players"),
+            _ => {
+                files[i] = Some(file);
+                Ok(())
+            }
+        };
+        let mut last_index = None;
+        for char in field.chars() {
+            let color = Color::from(char.is_ascii_uppercase());
+            let rank = if color.is_white() { 0 } else { 7 };
+            let is_piece = |file: u8, piece: PieceType| {
+                let sq = Square::from_coords(file, rank).expect("file is valid");
+                piece_locs[sq.to_index()] == Some(Piece::new(piece, color))
+            };
+            let king_file = (0..8).find(|&file| is_piece(file, PieceType::King));
+            let standard = king_file.is_none_or(|king| king == 4);
+            let (kingside, rook_file) = match char.to_ascii_uppercase() {
+                'K' if standard => (true, 7),
+                'Q' if standard => (false, 0),
+                'K' => (
+                    true,
+                    king_file
+                        .and_then(|king| {
+                            (king + 1..8).rev().find(|&f| is_piece(f, PieceType::Rook))
+                        })
+                        .unwrap_or(7),
+                ),
+                'Q' => (
+                    false,
+                    king_file
+                        .and_then(|king| (0..king).find(|&f| is_piece(f, PieceType::Rook)))
+                        .unwrap_or(0),
+                ),
+                file @ 'A'..='H' => {
+                    let file = file as u8 - b'A';
+                    let king_file =
+                        king_file.ok_or("Castling rights need the king on the back rank")?;
+                    if file == king_file {
+                        return Err("Castling file is the king's file");
+                    }
+                    (file > king_file, file)
+                }
+                _ => return Err("Invalid characters in castling input"),
+            };
+            if let Some(king_file) = king_file {
+                set_file(0, king_file)?;
+            }
+            set_file(if kingside { 1 } else { 2 }, rook_file)?;
+
+            let index = match (color, kingside) {
+                (Color::White, true) => 0,
+                (Color::White, false) => 1,
+                (Color::Black, true) => 2,
+                (Color::Black, false) => 3,
+            };
+            if last_index.is_some_and(|last| index <= last) {
+                return Err("Out of order castling");
+            }
+            last_index = Some(index);
+            match index {
+                0 => castling.white_kingside = true,
+                1 => castling.white_queenside = true,
+                2 => castling.black_kingside = true,
+                _ => castling.black_queenside = true,
+            }
+        }
+        let [king_file, kingside_rook_file, queenside_rook_file] = files;
+        Ok(CastlingRights {
+            king_file: king_file.unwrap_or(castling.king_file),
+            kingside_rook_file: kingside_rook_file.unwrap_or(castling.kingside_rook_file),
+            queenside_rook_file: queenside_rook_file.unwrap_or(castling.queenside_rook_file),
+            ..castling
+        })
+    }
+    /// Returns whether the given player can still castle kingside
+    pub fn kingside(&self, color: impl Into<Color>) -> bool {
+        match color.into() {
+            Color::White => self.white_kingside,
+            Color::Black => self.black_kingside,
+        }
+    }
+    /// Returns whether the given player can still castle queenside
+    pub fn queenside(&self, color: impl Into<Color>) -> bool {
+        match color.into() {
+            Color::White => self.white_queenside,
+            Color::Black => self.black_queenside,
+        }
+    }
 }
 impl Default for CastlingRights {
     fn default() -> Self {
@@ -1153,12 +2145,231 @@ impl Default for CastlingRights {
             white_queenside: true,
             black_kingside: true,
             black_queenside: true,
+            king_file: 4,
+            kingside_rook_file: 7,
+            queenside_rook_file: 0,
         }
     }
 }
+impl Display for CastlingRights {
+    /// Formats the rights as the castling field of a FEN string, using `KQkq` for the standard
+    /// files and the rook files as in Shredder-FEN otherwise
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let standard =
+            self.king_file == 4 && self.kingside_rook_file == 7 && self.queenside_rook_file == 0;
+        let rights = [
+            (
+                self.white_kingside,
+                'K',
+                self.kingside_rook_file,
+                Color::White,
+            ),
+            (
+                self.white_queenside,
+                'Q',
+                self.queenside_rook_file,
+                Color::White,
+            ),
+            (
+                self.black_kingside,
+                'K',
+                self.kingside_rook_file,
+                Color::Black,
+            ),
+            (
+                self.black_queenside,
+                'Q',
+                self.queenside_rook_file,
+                Color::Black,
+            ),
+        ];
+        let mut castling = String::new();
+        for (_, side, file, color) in rights.into_iter().filter(|right| right.0) {
+            let char = if standard {
+                side
+            } else {
+                (b'A' + file) as char
+            };
+            castling.push(if color.is_white() {
+                char
+            } else {
+                char.to_ascii_lowercase()
+            });
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+        write!(f, "{castling}")
+    }
+}
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    fn play_moves(board: &mut ChessBoard, moves: &[&str]) {
+        for r#move in moves {
+            let turn = r#move.parse().expect("valid notation");
+            let turn = board.validate_and_complete_turn(turn).expect("legal move");
+            board.update_board(&turn);
+        }
+    }
+
+    #[test]
+    fn game_state_messages() {
+        let messages = [
+            (
+                GameState::Win(Win {
+                    is_white: true,
+                    kind: WinType::Checkmate,
+                }),
+                "White wins by checkmate",
+            ),
+            (
+                GameState::Win(Win {
+                    is_white: false,
+                    kind: WinType::Timeout,
+                }),
+                "Black wins by timeout",
+            ),
+            (
+                GameState::Draw(DrawType::ThreefoldRepetition),
+                "The game is a draw by threefold repetition",
+            ),
+            (
+                GameState::Draw(DrawType::SeventyFiveMove),
+                "The game is a draw by the seventy-five move rule",
+            ),
+            (GameState::Stop, "The game was aborted"),
+            (GameState::Continue, "The game is in progress"),
+        ];
+        for (state, message) in messages {
+            assert_eq!(state.to_string(), message);
+        }
+        assert_eq!(WinType::Resign.to_string(), "resignation");
+        assert_eq!(DrawType::Agreement.to_string(), "agreement");
+        #[allow(deprecated)]
+        let old_name = DrawType::ThreefoldRepitition;
+        assert_eq!(old_name, DrawType::ThreefoldRepetition);
+        assert_eq!(
+            Outcome::Draw(DrawType::Stalemate).to_string(),
+            "The game is a draw by stalemate"
+        );
+    }
+    #[test]
+    fn turn_error_messages() {
+        let messages = [
+            (TurnError::NoTarget, "That piece can't move to that square"),
+            (
+                TurnError::NeedLine,
+                "Need to specify the line that the piece comes from",
+            ),
+            (
+                TurnError::NeedFile,
+                "Need to specify the file the pawn captures from",
+            ),
+            (
+                TurnError::NeedSquare,
+                "Need to specify the square the piece comes from",
+            ),
+            (
+                TurnError::OverSpecification,
+                "Provided unneeded line information",
+            ),
+            (
+                TurnError::MissingAtSquare,
+                "No piece that can move to the destination found at that square",
+            ),
+            (
+                TurnError::MissingInLine,
+                "No piece that can move to the destination found in that line",
+            ),
+            (
+                TurnError::BothInLine,
+                "Both potential pieces found in the line specified",
+            ),
+            (
+                TurnError::KingInCheck,
+                "That move causes the king to be in check",
+            ),
+            (TurnError::CastleLostRights, "Lost the right to castle"),
+            (
+                TurnError::CastlePathBlocked,
+                "Can't castle because the path is blocked",
+            ),
+            (
+                TurnError::CastleThroughCheck,
+                "Can't castle because the king would move through check",
+            ),
+            (
+                TurnError::NeedCheckSpecifier,
+                "Need to add a `+` when giving a check",
+            ),
+            (
+                TurnError::NeedCheckmateSpecifier,
+                "Need to add a `#` when giving checkmate",
+            ),
+            (
+                TurnError::NeedCaptureSpecifier,
+                "Need to add a `x` when capturing a piece",
+            ),
+            (
+                TurnError::RemoveCheckmateSpecifier,
+                "Remove `#` when not giving checkmate",
+            ),
+            (
+                TurnError::RemoveCheckSpecifier,
+                "Remove `+` when not giving check",
+            ),
+            (
+                TurnError::RemoveCaptureSpecifier,
+                "Remove `x` when not capturing a piece",
+            ),
+            (
+                TurnError::InvalidNotation("x?".parse::<Turn>().unwrap_err()),
+                "That move isn't valid chess notation",
+            ),
+            (
+                TurnError::NullMoveInCheck,
+                "Can't pass the turn while in check",
+            ),
+            (
+                TurnError::NullMoveNotAllowed,
+                "Null moves aren't allowed in this game",
+            ),
+            (
+                TurnError::InvalidPromotion(PieceType::King),
+                "Cannot promote a pawn into a K",
+            ),
+        ];
+        for (error, message) in messages {
+            // fails to compile when a variant is added without a message in the table above
+            match error {
+                TurnError::NoTarget
+                | TurnError::NeedLine
+                | TurnError::NeedFile
+                | TurnError::NeedSquare
+                | TurnError::OverSpecification
+                | TurnError::MissingAtSquare
+                | TurnError::MissingInLine
+                | TurnError::BothInLine
+                | TurnError::KingInCheck
+                | TurnError::CastleLostRights
+                | TurnError::CastlePathBlocked
+                | TurnError::CastleThroughCheck
+                | TurnError::NeedCheckSpecifier
+                | TurnError::NeedCheckmateSpecifier
+                | TurnError::NeedCaptureSpecifier
+                | TurnError::RemoveCheckmateSpecifier
+                | TurnError::RemoveCheckSpecifier
+                | TurnError::RemoveCaptureSpecifier
+                | TurnError::InvalidNotation(_)
+                | TurnError::NullMoveInCheck
+                | TurnError::NullMoveNotAllowed
+                | TurnError::InvalidPromotion(_) => (),
+            }
+            assert_eq!(error.to_string(), message);
+        }
+    }
     #[test]
     fn parse_fen() {
         let test = "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2";
@@ -1166,4 +2377,1095 @@ mod tests {
         let test = "lsefw sefwoe fjwofnwf weefwlkfn wlefkwlkfn sdf";
         assert!(test.parse::<ChessBoard>().is_err());
     }
+    #[test]
+    fn illegal_positions() {
+        let cases = [
+            (
+                "rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1",
+                PositionError::MissingKing(Color::Black),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQ1BNR w kq - 0 1",
+                PositionError::MissingKing(Color::White),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBKKBNR w kq - 0 1",
+                PositionError::TooManyKings(Color::White),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K2P w - - 0 1",
+                PositionError::PawnOnBackRank(Square::H1),
+            ),
+            (
+                "p3k3/8/8/8/8/8/8/4K3 w - - 0 1",
+                PositionError::PawnOnBackRank(Square::A8),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K2r b - - 0 1",
+                PositionError::OppositeKingInCheck,
+            ),
+        ];
+        for (fen, error) in cases {
+            assert_eq!(fen.parse::<ChessBoard>(), Err(error.reason()));
+        }
+        assert!("4k3/8/8/8/8/8/8/4K2r w - - 0 1"
+            .parse::<ChessBoard>()
+            .is_ok());
+
+        let mut board = ChessBoard::default();
+        assert_eq!(board.validate_position(), Ok(()));
+        board.remove_piece(Square::E8);
+        assert_eq!(
+            board.validate_position(),
+            Err(PositionError::MissingKing(Color::Black))
+        );
+        board.set_piece(Square::E8, Piece::new(PieceType::King, false));
+        board.set_piece(Square::D1, Piece::new(PieceType::Pawn, true));
+        assert_eq!(
+            board.validate_position(),
+            Err(PositionError::PawnOnBackRank(Square::D1))
+        );
+    }
+    #[test]
+    fn fen_round_trip() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 3",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "r3k3/8/8/8/8/8/8/4K2R b Kq - 0 1",
+            "8/5k2/8/8/8/8/2K5/8 b - - 99 1234",
+            "8/8/8/8/8/8/8/K6k w - - 255 65535",
+        ];
+        for fen in fens {
+            let board = fen.parse::<ChessBoard>().unwrap();
+            assert_eq!(board.gen_fen(), fen);
+            assert_eq!(board.to_fen(), fen);
+        }
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut board = ChessBoard::default();
+        play_moves(&mut board, &["e4", "c5", "Nf3", "d6", "d4", "cxd4"]);
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(json, format!("\"{}\"", board.gen_fen()));
+        assert_eq!(serde_json::from_str::<ChessBoard>(&json).unwrap(), board);
+
+        let turn = "Nxd4".parse::<Turn>().unwrap();
+        let json = serde_json::to_string(&turn).unwrap();
+        assert_eq!(serde_json::from_str::<Turn>(&json).unwrap(), turn);
+
+        let state = GameState::Draw(DrawType::Stalemate);
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(serde_json::from_str::<GameState>(&json).unwrap(), state);
+    }
+    #[test]
+    fn zobrist_transposition() {
+        let mut board1 = ChessBoard::default();
+        play_moves(&mut board1, &["Nf3", "Nf6", "Nc3", "Nc6"]);
+        let mut board2 = ChessBoard::default();
+        play_moves(&mut board2, &["Nc3", "Nc6", "Nf3", "Nf6"]);
+        assert_ne!(board1.gen_fen(), ChessBoard::default().gen_fen());
+        assert_eq!(board1.zobrist_hash(), board2.zobrist_hash());
+
+        play_moves(&mut board1, &["Ng1"]);
+        assert_ne!(board1.zobrist_hash(), board2.zobrist_hash());
+
+        // the en passant square only matters when it can be captured
+        let mut board = ChessBoard::default();
+        play_moves(&mut board, &["e4"]);
+        let no_en_passant = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert_eq!(board.zobrist_hash(), no_en_passant.zobrist_hash());
+        let can_capture = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        let cannot_capture = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert_ne!(can_capture.zobrist_hash(), cannot_capture.zobrist_hash());
+    }
+    #[test]
+    fn fen_malformed() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/44/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/08/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w QK - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KK - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w K- - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1",
+        ];
+        for fen in fens {
+            assert!(fen.parse::<ChessBoard>().is_err(), "{fen}");
+        }
+    }
+    #[test]
+    fn en_passant_removes_captured_pawn() {
+        let mut board = ChessBoard::default();
+        play_moves(&mut board, &["e4", "d5", "exd5", "e5", "dxe6"]);
+        assert_eq!(board.get(&Square::E5), None);
+        assert_eq!(board.get(&Square::D5), None);
+        assert_eq!(
+            board.get(&Square::E6),
+            Some(&Piece::new(PieceType::Pawn, true))
+        );
+    }
+    #[test]
+    fn positional_key() {
+        let mut board = ChessBoard::default();
+        play_moves(&mut board, &["Nf3", "Nf6", "Nc3", "Nc6"]);
+        let mut transposed = ChessBoard::default();
+        play_moves(&mut transposed, &["Nc3", "Nc6", "Nf3", "Nf6"]);
+        assert_eq!(board.positional_key(), transposed.positional_key());
+
+        let mut later = ChessBoard::default();
+        play_moves(&mut later, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+        play_moves(&mut later, &["Nc3", "Nc6", "Nf3", "Nf6"]);
+        assert_ne!(later, board);
+        assert_eq!(later.positional_key(), board.positional_key());
+
+        play_moves(&mut later, &["e4"]);
+        assert_ne!(later.positional_key(), board.positional_key());
+        // an en passant square that can't be used doesn't change the position
+        let no_en_passant = "r1bqkb1r/pppppppp/2n2n2/8/4P3/2N2N2/PPPP1PPP/R1BQKB1R b KQkq - 0 3"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert_eq!(later.positional_key(), no_en_passant.positional_key());
+    }
+    #[test]
+    fn null_move() {
+        let mut board = ChessBoard::default();
+        play_moves(&mut board, &["e4"]);
+        let before = board.clone();
+        let null = board.validate_and_complete_turn(Turn::Null).unwrap();
+        let token = board.make_unchecked(&null);
+        assert_eq!(board.side_to_move(), Color::White);
+        assert_eq!(board.en_passant_square(), None);
+        assert_eq!(board.half_move_clock(), 1);
+        assert_eq!(board.full_move_number(), 2);
+        assert_eq!(board.to_san(&Turn::Null), "--");
+        board.unmake(token);
+        assert_eq!(board, before);
+
+        let board = "4k3/8/8/8/8/8/8/4KR2 b - - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert!(board.validate_and_complete_turn(Turn::Null).is_ok());
+        let in_check = "4k3/8/8/8/8/8/8/4R1K1 b - - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert!(matches!(
+            in_check.validate_and_complete_turn(Turn::Null),
+            Err(TurnError::NullMoveInCheck)
+        ));
+    }
+    #[test]
+    fn find_king() {
+        let board = ChessBoard::default();
+        assert_eq!(board.find_king(Color::White), Some(Square::E1));
+        assert_eq!(board.find_king(false), Some(Square::E8));
+
+        let mut board = ChessBoard::default();
+        board.remove_piece(Square::E8);
+        assert_eq!(board.find_king(Color::Black), None);
+        assert!(!board.is_in_check(Color::Black));
+    }
+    #[test]
+    fn try_move() {
+        let board = ChessBoard::default();
+        let after = board.try_move(&"e4".parse().unwrap()).unwrap();
+        assert_eq!(
+            after.gen_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+        assert_eq!(board, ChessBoard::default());
+
+        assert!(matches!(
+            board.try_move(&"e5".parse().unwrap()),
+            Err(TurnError::NoTarget)
+        ));
+        assert!(matches!(
+            board.try_move(&"O-O".parse().unwrap()),
+            Err(TurnError::CastlePathBlocked)
+        ));
+        assert_eq!(board, ChessBoard::default());
+    }
+    #[test]
+    fn san_to_turn() {
+        let mut board = ChessBoard::default();
+        play_moves(&mut board, &["d4", "d5", "Nf3", "Nf6"]);
+        let turn = board.san_to_turn("Nbd2").unwrap();
+        assert_eq!(turn.to_uci(&board), "b1d2");
+        assert_eq!(
+            turn,
+            Turn::Move(Move::new(PieceType::Knight, Square::D2).from(Source::Square(Square::B1)))
+        );
+        assert_eq!(board.san_to_turn("Nfd2").unwrap().to_uci(&board), "f3d2");
+
+        assert!(matches!(
+            board.san_to_turn("Nd2"),
+            Err(crate::Error::Turn(TurnError::NeedLine))
+        ));
+        assert!(matches!(
+            board.san_to_turn("Nd9"),
+            Err(crate::Error::Parse(_))
+        ));
+    }
+    #[test]
+    #[cfg(feature = "std")]
+    fn apply_san() {
+        let mut board = ChessBoard::default();
+        let turn = board.apply_san("e4").unwrap();
+        assert!(matches!(
+            turn,
+            Turn::Move(Move {
+                piece: PieceType::Pawn,
+                dst: Square::E4,
+                src: Some(Source::Square(Square::E2)),
+                ..
+            })
+        ));
+        assert_eq!(
+            board.piece_at(Square::E4),
+            Some(Piece::new(PieceType::Pawn, true))
+        );
+
+        play_moves(&mut board, &["e5", "Bc4", "Nc6", "Qh5", "Nf6"]);
+        let mate = board.apply_san("Qxf7").unwrap();
+        assert!(mate.is_checkmate());
+        let fen = board.gen_fen();
+        assert!(board.apply_san("Ke7").is_err());
+        assert!(board.apply_san("Zz9").is_err());
+        assert_eq!(board.gen_fen(), fen);
+    }
+    #[test]
+    fn needs_promotion() {
+        let board = "k2r4/4P3/8/8/8/8/8/4K3 w - - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        let pawn = (Square::E7, *board.get(&Square::E7).unwrap());
+        let push = Turn::new(pawn, Square::E8);
+        assert!(board.needs_promotion(&push));
+        assert!(board.needs_promotion(&Turn::new(pawn, Square::D8)));
+        assert!(!board.needs_promotion(&push.with_promotion(PieceType::Knight)));
+        assert!(board
+            .validate_and_complete_turn(push.with_promotion(PieceType::Knight))
+            .is_ok());
+        for piece in [PieceType::King, PieceType::Pawn] {
+            assert!(matches!(
+                board.validate_and_complete_turn(push.with_promotion(piece)),
+                Err(TurnError::InvalidPromotion(invalid)) if invalid == piece
+            ));
+        }
+        // illegal moves don't need a promotion piece
+        assert!(!board.needs_promotion(&Turn::new(pawn, Square::F8)));
+        let king = (Square::E1, *board.get(&Square::E1).unwrap());
+        assert!(!board.needs_promotion(&Turn::new(king, Square::E2)));
+
+        let board = "k7/8/8/8/8/8/p7/4K3 b - - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        let pawn = (Square::A2, *board.get(&Square::A2).unwrap());
+        assert!(board.needs_promotion(&Turn::new(pawn, Square::A1)));
+    }
+    #[test]
+    fn is_en_passant() {
+        let mut board = ChessBoard::default();
+        play_moves(&mut board, &["e4", "d5", "e5", "f5"]);
+        let turn = |notation: &str| notation.parse::<Turn>().unwrap();
+        assert!(board.is_en_passant(&turn("exf6")));
+        assert!(board.is_en_passant(&board.validate_and_complete_turn(turn("exf6")).unwrap()));
+        assert!(!board.is_en_passant(&turn("e6")));
+        assert!(!board.is_en_passant(&turn("Nf3")));
+        assert!(!board.is_en_passant(&Turn::Castling(CastlingType::Short, flags::NONE)));
+
+        play_moves(&mut board, &["Nf3", "Nc6"]);
+        assert!(!board.is_en_passant(&turn("exf6")));
+    }
+    #[test]
+    fn fifty_move_rule() {
+        let mut board = ChessBoard::default();
+        for _ in 0..25 {
+            assert_eq!(board.check_gamestate(&Counter::new()), GameState::Continue);
+            play_moves(&mut board, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+        }
+        assert_eq!(board.half_move_clock, 100);
+        assert_eq!(board.check_gamestate(&Counter::new()), GameState::Continue);
+        assert_eq!(
+            board.claimable_draw(&Counter::new()),
+            Some(DrawType::FiftyMove)
+        );
+        for _ in 0..12 {
+            play_moves(&mut board, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+        }
+        play_moves(&mut board, &["Nf3", "Nf6"]);
+        assert_eq!(board.half_move_clock, 150);
+        assert_eq!(
+            board.check_gamestate(&Counter::new()),
+            GameState::Draw(DrawType::SeventyFiveMove)
+        );
+
+        let mut board = ChessBoard::default();
+        play_moves(&mut board, &["Nf3", "Nf6", "e4"]);
+        assert_eq!(board.half_move_clock, 0);
+        play_moves(&mut board, &["Nxe4"]);
+        assert_eq!(board.half_move_clock, 0);
+        play_moves(&mut board, &["Nc3"]);
+        assert_eq!(board.half_move_clock, 1);
+    }
+    #[test]
+    fn mandatory_draws() {
+        let mut board = ChessBoard::default();
+        let mut position_hist = Counter::new();
+        for _ in 0..4 {
+            assert_eq!(board.check_gamestate(&position_hist), GameState::Continue);
+            for r#move in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+                position_hist.add(board.zobrist_hash());
+                play_moves(&mut board, &[r#move]);
+            }
+        }
+        assert_eq!(
+            board.claimable_draw(&position_hist),
+            Some(DrawType::ThreefoldRepetition)
+        );
+        assert!(board.is_threefold_repetition(&position_hist));
+        #[allow(deprecated)]
+        let threefold = board.is_threefold_repitition(&position_hist);
+        assert!(threefold);
+        assert_eq!(
+            board.check_gamestate(&position_hist),
+            GameState::Draw(DrawType::FivefoldRepetition)
+        );
+
+        // checkmate on the last move takes precedence over the seventy-five move rule
+        let board = "7k/8/6K1/8/8/8/Q7/8 w - - 149 100"
+            .parse::<ChessBoard>()
+            .unwrap();
+        let mut mate = board.clone();
+        play_moves(&mut mate, &["Qa8#"]);
+        assert_eq!(
+            mate.check_gamestate(&Counter::new()),
+            GameState::Win(Win {
+                is_white: true,
+                kind: WinType::Checkmate
+            })
+        );
+        let mut no_mate = board.clone();
+        play_moves(&mut no_mate, &["Qb1"]);
+        assert_eq!(
+            no_mate.check_gamestate(&Counter::new()),
+            GameState::Draw(DrawType::SeventyFiveMove)
+        );
+    }
+    #[test]
+    fn long_castle_with_attacked_b_file() {
+        let board = "1r2k3/8/8/8/8/8/8/R3K3 w Q - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert!(board
+            .validate_and_complete_turn(Turn::Castling(CastlingType::Long, flags::NONE))
+            .is_ok());
+
+        let board = "4k3/8/8/8/8/8/8/RN2K3 w Q - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert!(matches!(
+            board.validate_and_complete_turn(Turn::Castling(CastlingType::Long, flags::NONE)),
+            Err(TurnError::CastlePathBlocked)
+        ));
+
+        let board = "2r1k3/8/8/8/8/8/8/R3K3 w Q - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert!(matches!(
+            board.validate_and_complete_turn(Turn::Castling(CastlingType::Long, flags::NONE)),
+            Err(TurnError::CastleThroughCheck)
+        ));
+    }
+    #[test]
+    fn legal_moves() {
+        assert_eq!(ChessBoard::default().legal_moves().len(), 20);
+
+        let board = "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4"
+            .parse::<ChessBoard>()
+            .unwrap();
+        let mate = Turn::Move(Move {
+            piece: PieceType::Queen,
+            dst: Square::F7,
+            flags: flags::NONE,
+            src: Some(Source::Square(Square::H5)),
+            promotion: None,
+        });
+        assert!(board.legal_moves().contains(&mate));
+
+        let board = "4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        let moves = board.legal_moves();
+        assert!(moves.contains(&Turn::Castling(CastlingType::Short, flags::NONE)));
+        assert!(moves.contains(&Turn::Castling(CastlingType::Long, flags::NONE)));
+    }
+    #[test]
+    fn perft_starting_position() {
+        let board = ChessBoard::default();
+        assert_eq!(board.perft(1), 20);
+        assert_eq!(board.perft(2), 400);
+        assert_eq!(board.perft(3), 8902);
+        assert_eq!(board.perft(4), 197281);
+    }
+    #[test]
+    fn perft_kiwipete() {
+        let board = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert_eq!(board.perft(1), 48);
+        assert_eq!(board.perft(2), 2039);
+        assert_eq!(board.perft(3), 97862);
+    }
+    #[test]
+    fn perft_en_passant_pins() {
+        let board = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert_eq!(board.perft(1), 14);
+        assert_eq!(board.perft(2), 191);
+        assert_eq!(board.perft(3), 2812);
+        assert_eq!(board.perft(4), 43238);
+    }
+    #[test]
+    fn chess960_castling() {
+        // the king lands on the rook's square
+        let mut board = "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9"
+            .parse::<ChessBoard>()
+            .unwrap();
+        let castle = Turn::Castling(CastlingType::Short, flags::NONE);
+        assert!(board.legal_moves().contains(&castle));
+        board.update_board(&castle);
+        assert_eq!(
+            board.gen_fen(),
+            "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRRKB b - - 2 9"
+        );
+
+        // the king doesn't move
+        let mut board = "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert!(matches!(
+            board.validate_castling(&CastlingType::Short, &flags::NONE),
+            Err(TurnError::CastlePathBlocked)
+        ));
+        board.remove_piece(Square::F1);
+        board.update_board(&castle);
+        assert_eq!(
+            board.gen_fen(),
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRRK1 b he - 2 9"
+        );
+
+        // the castling rook was shielding the king's destination
+        let board = "4k3/8/8/8/8/8/8/qRK5 w B - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert!(matches!(
+            board.validate_castling(&CastlingType::Long, &flags::NONE),
+            Err(TurnError::CastleThroughCheck)
+        ));
+    }
+    #[test]
+    fn perft_promotions() {
+        let board = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert_eq!(board.perft(1), 6);
+        assert_eq!(board.perft(2), 264);
+        assert_eq!(board.perft(3), 9467);
+        let board = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert_eq!(board.perft(1), 44);
+        assert_eq!(board.perft(2), 1486);
+        assert_eq!(board.perft(3), 62379);
+    }
+    #[test]
+    fn promotion_moves() {
+        let promotions = |fen: &str| {
+            let board = fen.parse::<ChessBoard>().unwrap();
+            board
+                .legal_moves()
+                .into_iter()
+                .filter(|turn| matches!(turn, Turn::Move(r#move) if r#move.is_promotion()))
+                .collect::<Vec<_>>()
+        };
+        let pushes = promotions("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+        assert_eq!(pushes.len(), 4);
+        for piece in [
+            PieceType::Queen,
+            PieceType::Rook,
+            PieceType::Bishop,
+            PieceType::Knight,
+        ] {
+            assert!(pushes
+                .iter()
+                .any(|turn| matches!(turn, Turn::Move(r#move) if r#move.promotion == Some(piece))));
+        }
+        assert_eq!(promotions("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1").len(), 8);
+        assert_eq!(promotions("1r2k3/1P6/8/8/8/8/8/4K3 w - - 0 1").len(), 0);
+    }
+    #[test]
+    fn perft_chess960() {
+        let cases = [
+            (
+                "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+                [21, 528, 12189],
+            ),
+            (
+                "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+                [21, 807, 18002],
+            ),
+            (
+                "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+                [20, 479, 10471],
+            ),
+        ];
+        for (fen, nodes) in cases {
+            let board = fen.parse::<ChessBoard>().unwrap();
+            for (depth, nodes) in (1..).zip(nodes) {
+                assert_eq!(board.perft(depth), nodes, "{fen} at depth {depth}");
+            }
+        }
+    }
+    #[test]
+    fn checkmate_and_stalemate() {
+        let back_rank_mate = "3R2k1/5ppp/8/8/8/8/8/6K1 b - - 1 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert!(back_rank_mate.is_checkmate());
+        assert!(!back_rank_mate.is_stalemate());
+
+        let pawn_stalemate = "4k3/4P3/4K3/8/8/8/8/8 b - - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert!(pawn_stalemate.is_stalemate());
+        assert!(!pawn_stalemate.is_checkmate());
+
+        let board = ChessBoard::default();
+        assert!(!board.is_checkmate());
+        assert!(!board.is_stalemate());
+    }
+    #[test]
+    fn legal_destinations() {
+        let board = ChessBoard::default();
+        let mut knight = board.legal_destinations(Square::B1);
+        knight.sort_by_key(|sq| sq.to_index());
+        assert_eq!(knight, vec![Square::A3, Square::C3]);
+        assert!(board.legal_destinations(Square::E4).is_empty());
+        assert!(board.legal_destinations(Square::G8).is_empty());
+        assert!(board.legal_destinations(Square::A1).is_empty());
+
+        // the knight is pinned, while the rook can still move along the pin
+        let board = "4r1k1/8/8/8/4N3/8/8/4K3 w - - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert!(board.legal_destinations(Square::E4).is_empty());
+        let board = "4r1k1/8/8/8/8/8/4R3/4K3 w - - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        let mut rook = board.legal_destinations(Square::E2);
+        rook.sort_by_key(|sq| sq.to_index());
+        assert_eq!(
+            rook,
+            vec![
+                Square::E8,
+                Square::E7,
+                Square::E6,
+                Square::E5,
+                Square::E4,
+                Square::E3
+            ]
+        );
+
+        let board = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        let king = board.legal_destinations(Square::E1);
+        assert_eq!(king.len(), 7);
+        assert!(king.contains(&Square::G1) && king.contains(&Square::C1));
+    }
+    #[test]
+    fn mate_in_one() {
+        let board = "6k1/5ppp/8/4n3/8/2B5/8/R5K1 w - - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        let mates = board.mate_in_one();
+        assert_eq!(mates.len(), 1);
+        assert_eq!(mates[0].to_uci(&board), "a1a8");
+
+        // both rooks can mate on the back rank
+        let board = "6k1/5ppp/8/8/8/8/8/R2R2K1 w - - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        let mut mates: Vec<String> = board
+            .mate_in_one()
+            .iter()
+            .map(|turn| turn.to_uci(&board))
+            .collect();
+        mates.sort();
+        assert_eq!(mates, vec!["a1a8", "d1d8"]);
+
+        assert!(ChessBoard::default().mate_in_one().is_empty());
+    }
+    #[test]
+    fn gives_check_and_checkmate() {
+        let mut board = ChessBoard::default();
+        play_moves(&mut board, &["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6"]);
+        let turn = |notation: &str| notation.parse::<Turn>().unwrap();
+
+        assert!(board.gives_check(&turn("Qxf7")));
+        assert!(board.gives_checkmate(&turn("Qxf7")));
+        assert!(board.gives_check(&turn("Bxf7")));
+        assert!(!board.gives_checkmate(&turn("Bxf7")));
+        assert!(!board.gives_check(&turn("Nf3")));
+        // illegal turns neither check nor mate
+        assert!(!board.gives_check(&turn("Qxf6")));
+        assert!(!board.gives_checkmate(&turn("Qa7")));
+    }
+    #[test]
+    fn castling_prevents_stalemate() {
+        // the king can't leave g1, and the rook on h1 can only move by castling to f1
+        let board = "k4r2/8/8/8/8/7b/7P/6KR w H - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert_eq!(
+            board.legal_moves(),
+            vec![Turn::Castling(CastlingType::Short, flags::NONE)]
+        );
+        assert_eq!(board.check_gamestate(&Counter::new()), GameState::Continue);
+    }
+    #[test]
+    fn pinned_pieces() {
+        // bishop on b4 pins the knight on d2 to the king
+        let board = "4k3/8/8/8/1b6/8/3N4/4K3 w - - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert_eq!(
+            board.pinned_pieces(Color::White),
+            vec![(Square::D2, Square::B4)]
+        );
+        assert!(board.pinned_pieces(Color::Black).is_empty());
+
+        // the knight on d3 is off the bishop's diagonal and can move freely
+        let board = "4k3/8/8/8/1b6/3N4/8/4K3 w - - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert!(board.pinned_pieces(true).is_empty());
+        assert!(board
+            .legal_moves()
+            .iter()
+            .any(|turn| matches!(turn, Turn::Move(r#move) if r#move.piece == PieceType::Knight)));
+    }
+    #[test]
+    fn bordered_string() {
+        let board = ChessBoard::default();
+        let white = "   a b c d e f g h
+  ┌─────────────────┐
+8 │ ♜ ♞ ♝ ♛ ♚ ♝ ♞ ♜ │ 8
+7 │ ♟ ♟ ♟ ♟ ♟ ♟ ♟ ♟ │ 7
+6 │ · · · · · · · · │ 6
+5 │ · · · · · · · · │ 5
+4 │ · · · · · · · · │ 4
+3 │ · · · · · · · · │ 3
+2 │ ♙ ♙ ♙ ♙ ♙ ♙ ♙ ♙ │ 2
+1 │ ♖ ♘ ♗ ♕ ♔ ♗ ♘ ♖ │ 1
+  └─────────────────┘
+   a b c d e f g h
+";
+        assert_eq!(board.to_string_bordered(RotateBoard::White), white);
+        assert_eq!(board.to_string_bordered(RotateBoard::Rotate), white);
+        let black = board.to_string_bordered(RotateBoard::Black);
+        assert!(black.starts_with("   h g f e d c b a\n"));
+        assert!(black.contains("\n1 │ ♖ ♘ ♗ ♔ ♕ ♗ ♘ ♖ │ 1\n"));
+    }
+    #[test]
+    #[cfg(feature = "std")]
+    fn attacked_squares() {
+        use std::collections::HashSet;
+        let board = ChessBoard::default();
+        // all of ranks 2 and 3, plus the back rank squares defended by a neighbouring piece
+        let expected: HashSet<Square> = Square::ALL[40..56]
+            .iter()
+            .copied()
+            .chain([
+                Square::B1,
+                Square::C1,
+                Square::D1,
+                Square::E1,
+                Square::F1,
+                Square::G1,
+            ])
+            .collect();
+        assert_eq!(board.attacked_squares(Color::White), expected);
+        assert_eq!(board.attacked_squares(true), expected);
+        assert!(!board.attacked_squares(Color::Black).contains(&Square::E4));
+        assert!(board.attacked_squares(Color::Black).contains(&Square::E6));
+    }
+    #[test]
+    fn attackers() {
+        // rook on e8 pins the knight on e4 to the king
+        let mut board = "4r1k1/8/8/8/4N3/8/8/4K3 w - - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert!(!board.is_in_check(Color::White));
+        assert_eq!(
+            board.attackers_of(Square::E4, Color::Black),
+            vec![Square::E8]
+        );
+        assert!(board.attackers_of(Square::E1, Color::Black).is_empty());
+        assert!(board.causes_check(
+            &Turn::new(
+                (Square::E4, board.get(&Square::E4).copied().unwrap()),
+                Square::C5
+            ),
+            Color::White
+        ));
+
+        // knight on f3 gives check
+        let board = "4k3/8/8/8/8/5n2/8/4K2R w - - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert!(board.is_in_check(Color::White));
+        assert!(!board.is_in_check(false));
+        assert_eq!(
+            board.attackers_of(Square::E1, Color::Black),
+            vec![Square::F3]
+        );
+        assert_eq!(
+            board.attackers_of(Square::F3, Color::White),
+            Vec::<Square>::new()
+        );
+        assert_eq!(
+            board.attackers_of(Square::H3, Color::White),
+            vec![Square::H1]
+        );
+    }
+    #[test]
+    fn pieces_iterator() {
+        let board = ChessBoard::default();
+        let pieces = board.pieces().collect::<Vec<_>>();
+        assert_eq!(pieces.len(), 32);
+        for (sq, piece) in &pieces {
+            assert_eq!(board.piece_at(*sq), Some(*piece));
+            assert_eq!(pieces.iter().filter(|(other, _)| other == sq).count(), 1);
+        }
+        assert_eq!(
+            board.piece_at(Square::E1),
+            Some(Piece::new(PieceType::King, Color::White))
+        );
+        assert_eq!(board.piece_at(Square::E4), None);
+    }
+    #[test]
+    fn board_editor() {
+        let mut board = ChessBoard::default();
+        board.clear();
+        assert_eq!(board.pieces().count(), 0);
+        board.set_piece(Square::H8, Piece::new(PieceType::King, Color::Black));
+        board.set_piece(Square::G6, Piece::new(PieceType::King, Color::White));
+        board.set_piece(Square::G7, Piece::new(PieceType::Queen, Color::White));
+        board.set_side_to_move(Color::Black);
+        assert_eq!(board.gen_fen(), "7k/6Q1/6K1/8/8/8/8/8 b - - 0 1");
+        assert!(board.legal_moves().is_empty());
+        assert_eq!(
+            board.check_gamestate(&Counter::new()),
+            GameState::Win(Win {
+                is_white: true,
+                kind: WinType::Checkmate
+            })
+        );
+
+        // moving a rook off its square removes that castling right
+        let mut board = ChessBoard::default();
+        assert_eq!(
+            board.remove_piece(Square::H1),
+            Some(Piece::new(PieceType::Rook, Color::White))
+        );
+        assert_eq!(
+            board.gen_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w Qkq - 0 1"
+        );
+    }
+    #[test]
+    fn state_getters() {
+        let mut board = ChessBoard::default();
+        assert_eq!(board.en_passant_square(), None);
+        assert_eq!(board.castling_rights(), CastlingRights::default());
+        assert_eq!(board.half_move_clock(), 0);
+        assert_eq!(board.full_move_number(), 1);
+
+        play_moves(&mut board, &["e4"]);
+        assert_eq!(board.en_passant_square(), Some(Square::E3));
+        assert_eq!(board.half_move_clock(), 0);
+        assert_eq!(board.full_move_number(), 1);
+
+        play_moves(&mut board, &["e5", "Ke2"]);
+        assert_eq!(board.en_passant_square(), None);
+        assert_eq!(board.half_move_clock(), 1);
+        assert_eq!(board.full_move_number(), 2);
+        let rights = board.castling_rights();
+        assert!(!rights.kingside(Color::White));
+        assert!(!rights.queenside(Color::White));
+        assert!(rights.kingside(Color::Black));
+        assert!(rights.queenside(Color::Black));
+
+        play_moves(&mut board, &["Nf6", "Ke1", "Rg8"]);
+        assert_eq!(board.half_move_clock(), 4);
+        assert_eq!(board.full_move_number(), 4);
+        let rights = board.castling_rights();
+        assert!(!rights.kingside(Color::White));
+        assert!(!rights.kingside(Color::Black));
+        assert!(rights.queenside(Color::Black));
+    }
+    #[test]
+    fn chess960_castling_fen() {
+        let fen = "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w GBgb - 0 1";
+        let board = fen.parse::<ChessBoard>().unwrap();
+        let rights = board.castling_rights();
+        assert_eq!(rights.king_file(), 3);
+        assert_eq!(rights.kingside_rook_file(), 6);
+        assert_eq!(rights.queenside_rook_file(), 1);
+        assert!(rights.kingside(Color::White) && rights.queenside(Color::Black));
+        assert_eq!(board.gen_fen(), fen);
+        let x_fen = "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w KQkq - 0 1";
+        assert_eq!(x_fen.parse::<ChessBoard>(), Ok(board));
+
+        let fen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+        let board = fen.parse::<ChessBoard>().unwrap();
+        assert_eq!(board.castling_rights().kingside_rook_file(), 7);
+        assert_eq!(board.castling_rights().queenside_rook_file(), 5);
+        assert_eq!(board.gen_fen(), fen);
+        assert_eq!(
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9".parse(),
+            Ok(board)
+        );
+
+        let standard = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert_eq!(standard, ChessBoard::default());
+        assert_eq!(standard.gen_fen(), ChessBoard::default().gen_fen());
+
+        // with the king on the e-file, `KQkq` keep the standard rook files
+        for fen in [
+            "r3k2r/8/8/8/8/8/8/R3KR2 w KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K1R1 w KQkq - 0 1",
+        ] {
+            let board = fen.parse::<ChessBoard>().unwrap();
+            assert_eq!(board.castling_rights().kingside_rook_file(), 7);
+            assert_eq!(board.castling_rights().queenside_rook_file(), 0);
+            assert_eq!(board.gen_fen(), fen);
+            let moves = board.legal_moves();
+            assert!(!moves.contains(&Turn::Castling(CastlingType::Short, flags::NONE)));
+            assert!(moves.contains(&Turn::Castling(CastlingType::Long, flags::NONE)));
+        }
+
+        let errors = [
+            (
+                "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w BG - 0 1",
+                "Out of order castling",
+            ),
+            (
+                "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w Dd - 0 1",
+                "Castling file is the king's file",
+            ),
+            (
+                "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w Gh - 0 1",
+                "Castling files differ between the players",
+            ),
+            (
+                "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w Gx - 0 1",
+                "Invalid characters in castling input",
+            ),
+        ];
+        for (fen, error) in errors {
+            assert_eq!(fen.parse::<ChessBoard>(), Err(error));
+        }
+    }
+    #[test]
+    fn mirror() {
+        let board = "rnbqkbnr/ppp1pppp/8/8/3pP3/5N2/PPPP1PPP/RNBQKB1R b KQq e3 0 3"
+            .parse::<ChessBoard>()
+            .unwrap();
+        let mirrored = board.mirror();
+        assert_eq!(
+            mirrored.gen_fen(),
+            "rnbqkb1r/pppp1ppp/5n2/3Pp3/8/8/PPP1PPPP/RNBQKBNR w Qkq e6 0 3"
+        );
+        assert_eq!(mirrored.mirror(), board);
+        assert_eq!(mirrored.legal_moves().len(), board.legal_moves().len());
+        assert_eq!(mirrored.perft(2), board.perft(2));
+
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        let mirrored = kiwipete.mirror();
+        assert_eq!(mirrored.mirror(), kiwipete);
+        assert_eq!(mirrored.perft(2), 2039);
+        assert_eq!(
+            mirrored.is_in_check(Color::Black),
+            kiwipete.is_in_check(Color::White)
+        );
+    }
+    #[test]
+    fn san_figurine() {
+        let figurine = |fen: &str, src: Square, dst: Square, promotion: Option<PieceType>| {
+            let board = fen.parse::<ChessBoard>().unwrap();
+            let turn = match Turn::new((src, *board.get(&src).unwrap()), dst) {
+                Turn::Move(r#move) => Turn::Move(Move {
+                    promotion,
+                    ..r#move
+                }),
+                castling => castling,
+            };
+            board.to_san_figurine(&turn)
+        };
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(figurine(start, Square::G1, Square::F3, None), "\u{2658}f3");
+        assert_eq!(figurine(start, Square::E2, Square::E4, None), "e4");
+        assert_eq!(
+            figurine(
+                "4k3/8/8/8/8/8/8/4K1n1 b - - 0 1",
+                Square::G1,
+                Square::F3,
+                None
+            ),
+            "\u{265E}f3+"
+        );
+        assert_eq!(
+            figurine(
+                "k7/4P3/8/8/8/8/8/4K3 w - - 0 1",
+                Square::E7,
+                Square::E8,
+                Some(PieceType::Queen)
+            ),
+            "e8=\u{2655}+"
+        );
+        assert_eq!(
+            figurine(
+                "r3k2r/8/8/8/8/8/8/4K3 b kq - 0 1",
+                Square::E8,
+                Square::G8,
+                None
+            ),
+            "O-O"
+        );
+    }
+    #[test]
+    fn san() {
+        let san = |fen: &str, src: Square, dst: Square, promotion: Option<PieceType>| {
+            let board = fen.parse::<ChessBoard>().unwrap();
+            let turn = match Turn::new((src, *board.get(&src).unwrap()), dst) {
+                Turn::Move(r#move) => Turn::Move(Move {
+                    promotion,
+                    ..r#move
+                }),
+                castling => castling,
+            };
+            board.to_san(&turn)
+        };
+        assert_eq!(
+            san(
+                "rnbqkb1r/ppp1pppp/5n2/3p4/3P4/5N2/PPP1PPPP/RNBQKB1R w KQkq - 2 3",
+                Square::B1,
+                Square::D2,
+                None
+            ),
+            "Nbd2"
+        );
+        assert_eq!(
+            san(
+                "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+                Square::E4,
+                Square::D5,
+                None
+            ),
+            "exd5"
+        );
+        // only a pawn captures by moving onto the en passant square
+        assert_eq!(
+            san(
+                "rnbqkbnr/ppp2ppp/4p3/1N1p4/8/8/PPPPPPPP/R1BQKBNR w KQkq d6 0 3",
+                Square::B5,
+                Square::D6,
+                None
+            ),
+            "Nd6+"
+        );
+        assert_eq!(
+            san(
+                "k7/4P3/8/8/8/8/8/4K3 w - - 0 1",
+                Square::E7,
+                Square::E8,
+                Some(PieceType::Queen)
+            ),
+            "e8=Q+"
+        );
+        assert_eq!(
+            san(
+                "2rkr3/2p1p3/8/8/8/8/8/R3K3 w Q - 0 1",
+                Square::E1,
+                Square::C1,
+                None
+            ),
+            "O-O-O#"
+        );
+        assert_eq!(
+            san(
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                Square::G1,
+                Square::F3,
+                None
+            ),
+            "Nf3"
+        );
+    }
+    #[test]
+    fn material() {
+        let mut board = ChessBoard::default();
+        assert_eq!(board.material(Color::White), 39);
+        assert_eq!(board.material(Color::Black), 39);
+        assert_eq!(board.material_balance(), 0);
+        board.remove_piece(Square::A8);
+        assert_eq!(board.material(Color::Black), 34);
+        assert_eq!(board.material_balance(), 5);
+    }
+    #[test]
+    fn insufficient_material() {
+        let is_dead = |fen: &str| {
+            fen.parse::<ChessBoard>()
+                .unwrap()
+                .is_insufficient_material()
+        };
+        // KvK
+        assert!(is_dead("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        // KBvK
+        assert!(is_dead("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        // KNvK
+        assert!(is_dead("4k3/8/8/8/8/8/8/1N2K3 b - - 0 1"));
+        // KNNvK
+        assert!(!is_dead("4k3/8/8/8/8/8/8/1N2K1N1 w - - 0 1"));
+        // KBvKB with same colored bishops
+        assert!(is_dead("2b1k3/8/8/8/8/8/8/3BK3 w - - 0 1"));
+        // KBBvKB with all bishops on the same color
+        assert!(is_dead("2b1k3/8/8/8/8/8/8/3BKB2 w - - 0 1"));
+        // KBvKB with opposite colored bishops
+        assert!(!is_dead("2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        // KBvKN
+        assert!(!is_dead("1n2k3/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        // KPvK
+        assert!(!is_dead("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"));
+    }
 }
@@ -0,0 +1,97 @@
+use super::square::Square;
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING_OFFSETS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+const KNIGHT_ATTACKS: [u64; 64] = gen_attacks(&KNIGHT_OFFSETS);
+const KING_ATTACKS: [u64; 64] = gen_attacks(&KING_OFFSETS);
+
+/// Generates the attack table at compile time, where bit `i` of the entry for a square is set
+/// if the square with index `i` is one of the file and rank offsets away from it
+const fn gen_attacks(offsets: &[(i8, i8); 8]) -> [u64; 64] {
+    let mut table = [0; 64];
+    let mut sq = 0;
+    while sq < 64 {
+        let file = (sq % 8) as i8;
+        let rank = 7 - (sq / 8) as i8;
+        let mut i = 0;
+        while i < offsets.len() {
+            let (target_file, target_rank) = (file + offsets[i].0, rank + offsets[i].1);
+            if 0 <= target_file && target_file < 8 && 0 <= target_rank && target_rank < 8 {
+                table[sq] |= 1 << ((7 - target_rank) * 8 + target_file);
+            }
+            i += 1;
+        }
+        sq += 1;
+    }
+    table
+}
+
+/// Returns the squares that a knight on the square attacks
+pub(super) fn knight_attacks(sq: Square) -> impl Iterator<Item = Square> {
+    squares(KNIGHT_ATTACKS[sq.to_index()])
+}
+
+/// Returns the squares that a king on the square attacks
+pub(super) fn king_attacks(sq: Square) -> impl Iterator<Item = Square> {
+    squares(KING_ATTACKS[sq.to_index()])
+}
+
+fn squares(mut bits: u64) -> impl Iterator<Item = Square> {
+    core::iter::from_fn(move || {
+        if bits == 0 {
+            return None;
+        }
+        let index = bits.trailing_zeros() as usize;
+        bits &= bits - 1;
+        Some(Square::ALL[index])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn corner_knight() {
+        let mut targets = knight_attacks(Square::A1).collect::<Vec<_>>();
+        targets.sort_by_key(|sq| sq.to_index());
+        assert_eq!(targets, [Square::B3, Square::C2]);
+        assert_eq!(king_attacks(Square::H8).count(), 3);
+    }
+    #[test]
+    fn tables_match_offsets() {
+        for (table, offsets) in [
+            (KNIGHT_ATTACKS, KNIGHT_OFFSETS),
+            (KING_ATTACKS, KING_OFFSETS),
+        ] {
+            for sq in Square::iterator() {
+                let mut expected = offsets
+                    .iter()
+                    .filter_map(|&(file, rank)| sq.offset(file, rank))
+                    .collect::<Vec<_>>();
+                expected.sort_by_key(|sq| sq.to_index());
+                assert_eq!(squares(table[sq.to_index()]).collect::<Vec<_>>(), expected);
+            }
+        }
+    }
+}
@@ -1,8 +1,6 @@
-use std::collections::HashMap;
-
 use super::square::Square;
 use super::{CastlingRights, ChessBoard};
-use crate::pieces::{Piece, PieceType};
+use crate::pieces::{Color, Piece, PieceType};
 
 impl Default for ChessBoard {
     fn default() -> Self {
@@ -40,10 +38,13 @@ impl Default for ChessBoard {
             (Square::G7, Piece::new(PieceType::Pawn, false)),
             (Square::H7, Piece::new(PieceType::Pawn, false)),
         ];
-        let board = HashMap::from(board_array);
+        let mut piece_locs = [None; 64];
+        for (sq, piece) in board_array {
+            piece_locs[sq.to_index()] = Some(piece);
+        }
         ChessBoard {
-            piece_locs: board,
-            is_white: true,
+            piece_locs,
+            side_to_move: Color::White,
             castling: CastlingRights::default(),
             en_passant: None,
             half_move_clock: 0,
@@ -1,9 +1,11 @@
-use std::{fmt::Display, str::FromStr};
+use alloc::{borrow::ToOwned, vec::Vec};
+use core::{fmt::Display, str::FromStr};
 
 use super::square::Square;
 use lines::*;
 
 #[derive(Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[rustfmt::skip]
 #[allow(missing_docs)]
 /// A rank or file on the chess board
@@ -271,7 +273,7 @@ impl Line {
 impl IntoIterator for Line {
     type Item = Square;
 
-    type IntoIter = std::vec::IntoIter<Self::Item>;
+    type IntoIter = alloc::vec::IntoIter<Self::Item>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.to_vec().into_iter()
@@ -290,7 +292,7 @@ impl FromStr for Line {
 }
 
 impl Display for Line {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Line::Rank1 => write!(f, "1"),
             Line::Rank2 => write!(f, "2"),
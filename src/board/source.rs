@@ -1,9 +1,10 @@
-use std::fmt::Display;
+use core::fmt::Display;
 
 use super::line::Line;
 use super::square::Square;
 
 #[derive(Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The type of source square used for disambiguation
 pub enum Source {
     /// Line source, which can be a file or rank
@@ -13,7 +14,7 @@ pub enum Source {
 }
 
 impl Display for Source {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Source::Line(line) => write!(f, "{}", line),
             Source::Square(square) => write!(f, "{}", square),
@@ -1,9 +1,14 @@
-use std::{fmt::Display, str::FromStr};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt::Display, str::FromStr};
 
 use super::line::Line;
 use crate::parser::ConversionError;
 
 #[derive(Debug, Hash, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 #[rustfmt::skip]
 #[allow(missing_docs)]
@@ -20,6 +25,18 @@ pub enum Square {
 }
 
 impl Square {
+    /// Every square on the board, ordered from a8 to h1 rank by rank
+    #[rustfmt::skip]
+    pub const ALL: [Square; 64] = [
+        Self::A8, Self::B8, Self::C8, Self::D8, Self::E8, Self::F8, Self::G8, Self::H8,
+        Self::A7, Self::B7, Self::C7, Self::D7, Self::E7, Self::F7, Self::G7, Self::H7,
+        Self::A6, Self::B6, Self::C6, Self::D6, Self::E6, Self::F6, Self::G6, Self::H6,
+        Self::A5, Self::B5, Self::C5, Self::D5, Self::E5, Self::F5, Self::G5, Self::H5,
+        Self::A4, Self::B4, Self::C4, Self::D4, Self::E4, Self::F4, Self::G4, Self::H4,
+        Self::A3, Self::B3, Self::C3, Self::D3, Self::E3, Self::F3, Self::G3, Self::H3,
+        Self::A2, Self::B2, Self::C2, Self::D2, Self::E2, Self::F2, Self::G2, Self::H2,
+        Self::A1, Self::B1, Self::C1, Self::D1, Self::E1, Self::F1, Self::G1, Self::H1,
+    ];
     /// Returns the rank that the square is on
     pub fn rank(&self) -> Line {
         match self {
@@ -196,65 +213,98 @@ impl Square {
                 | Self::H1
         )
     }
+    /// Returns the square at the given index, where a8 is 0 and h1 is 63, matching the order of
+    /// [Square::iterator]. Returns `None` if the index is 64 or more
+    pub fn from_index(index: usize) -> Option<Square> {
+        Self::ALL.get(index).copied()
+    }
+    /// Returns the index of the square, where a8 is 0 and h1 is 63
+    pub fn to_index(self) -> usize {
+        self as usize
+    }
+    /// Returns the square at the given zero-indexed file and rank, where `(0, 0)` is a1 and
+    /// `(7, 7)` is h8. Returns `None` if either coordinate is off the board
+    pub fn from_coords(file: u8, rank: u8) -> Option<Square> {
+        if file > 7 || rank > 7 {
+            return None;
+        }
+        Some(Self::ALL[((7 - rank) * 8 + file) as usize])
+    }
+    /// Returns the zero-indexed file of the square, where the a-file is 0
+    pub fn file_index(&self) -> u8 {
+        *self as u8 % 8
+    }
+    /// Returns the zero-indexed rank of the square, where the first rank is 0
+    pub fn rank_index(&self) -> u8 {
+        7 - *self as u8 / 8
+    }
+    /// Returns the square that is `df` files to the right and `dr` ranks above the current
+    /// square, from the orientation of the white player. Returns `None` if it is off the board
+    pub fn offset(&self, df: i8, dr: i8) -> Option<Square> {
+        let file = self.file_index().checked_add_signed(df)?;
+        let rank = self.rank_index().checked_add_signed(dr)?;
+        Square::from_coords(file, rank)
+    }
+    /// Returns the number of king moves needed to go between the two squares
+    pub fn king_distance(&self, other: Square) -> u8 {
+        let file_distance = self.file_index().abs_diff(other.file_index());
+        let rank_distance = self.rank_index().abs_diff(other.rank_index());
+        file_distance.max(rank_distance)
+    }
+    /// Returns the sum of the file and rank distances between the two squares
+    pub fn manhattan_distance(&self, other: Square) -> u8 {
+        let file_distance = self.file_index().abs_diff(other.file_index());
+        let rank_distance = self.rank_index().abs_diff(other.rank_index());
+        file_distance + rank_distance
+    }
+    /// Returns whether the two squares are on the same diagonal running from the a1 side to the
+    /// h8 side, where the file and rank increase together
+    pub fn same_diagonal(&self, other: Square) -> bool {
+        self.file_index() as i8 - self.rank_index() as i8
+            == other.file_index() as i8 - other.rank_index() as i8
+    }
+    /// Returns whether the two squares are on the same anti-diagonal running from the a8 side to
+    /// the h1 side, where the file increases as the rank decreases
+    pub fn same_anti_diagonal(&self, other: Square) -> bool {
+        self.file_index() + self.rank_index() == other.file_index() + other.rank_index()
+    }
+    /// Returns the squares strictly between the two squares, ordered from `a` to `b`, when they
+    /// share a rank, file, or diagonal. Returns an empty vec otherwise
+    pub fn between(a: Square, b: Square) -> Vec<Square> {
+        let file_diff = b.file_index() as i8 - a.file_index() as i8;
+        let rank_diff = b.rank_index() as i8 - a.rank_index() as i8;
+        let collinear = file_diff == 0 || rank_diff == 0 || file_diff.abs() == rank_diff.abs();
+        if !collinear || a == b {
+            return Vec::new();
+        }
+        let (df, dr) = (file_diff.signum(), rank_diff.signum());
+        let steps = a.king_distance(b) as i8;
+        (1..steps)
+            .map(|step| {
+                a.offset(df * step, dr * step)
+                    .expect("is between valid squares")
+            })
+            .collect()
+    }
     /// Returns the square that is above the current square, from the orientation of the white
     /// player. Returns `None` if on the top rank
     pub fn up(&self) -> Option<Square> {
-        match self.rank() {
-            Line::Rank1 => Line::Rank2.intersection(&self.file()),
-            Line::Rank2 => Line::Rank3.intersection(&self.file()),
-            Line::Rank3 => Line::Rank4.intersection(&self.file()),
-            Line::Rank4 => Line::Rank5.intersection(&self.file()),
-            Line::Rank5 => Line::Rank6.intersection(&self.file()),
-            Line::Rank6 => Line::Rank7.intersection(&self.file()),
-            Line::Rank7 => Line::Rank8.intersection(&self.file()),
-            Line::Rank8 => None,
-            _ => unreachable!(),
-        }
+        self.offset(0, 1)
     }
     /// Returns the square that is below the current square, from the orientation of the white
     /// player. Returns `None` if on the bottom rank
     pub fn down(&self) -> Option<Square> {
-        match self.rank() {
-            Line::Rank1 => None,
-            Line::Rank2 => Line::Rank1.intersection(&self.file()),
-            Line::Rank3 => Line::Rank2.intersection(&self.file()),
-            Line::Rank4 => Line::Rank3.intersection(&self.file()),
-            Line::Rank5 => Line::Rank4.intersection(&self.file()),
-            Line::Rank6 => Line::Rank5.intersection(&self.file()),
-            Line::Rank7 => Line::Rank6.intersection(&self.file()),
-            Line::Rank8 => Line::Rank7.intersection(&self.file()),
-            _ => unreachable!(),
-        }
+        self.offset(0, -1)
     }
     /// Returns the square that is to the right of the current square, from the orientation of the white
     /// player. Returns `None` if on the rightmost file
     pub fn right(&self) -> Option<Square> {
-        match self.file() {
-            Line::FileA => Line::FileB.intersection(&self.rank()),
-            Line::FileB => Line::FileC.intersection(&self.rank()),
-            Line::FileC => Line::FileD.intersection(&self.rank()),
-            Line::FileD => Line::FileE.intersection(&self.rank()),
-            Line::FileE => Line::FileF.intersection(&self.rank()),
-            Line::FileF => Line::FileG.intersection(&self.rank()),
-            Line::FileG => Line::FileH.intersection(&self.rank()),
-            Line::FileH => None,
-            _ => unreachable!(),
-        }
+        self.offset(1, 0)
     }
     /// Returns the square that is to the left of the current square, from the orientation of the white
     /// player. Returns `None` if on the leftmost file
     pub fn left(&self) -> Option<Square> {
-        match self.file() {
-            Line::FileA => None,
-            Line::FileB => Line::FileA.intersection(&self.rank()),
-            Line::FileC => Line::FileB.intersection(&self.rank()),
-            Line::FileD => Line::FileC.intersection(&self.rank()),
-            Line::FileE => Line::FileD.intersection(&self.rank()),
-            Line::FileF => Line::FileE.intersection(&self.rank()),
-            Line::FileG => Line::FileF.intersection(&self.rank()),
-            Line::FileH => Line::FileG.intersection(&self.rank()),
-            _ => unreachable!(),
-        }
+        self.offset(-1, 0)
     }
     /// Returns the square that is to the up-right of the current square, from the orientation of the white
     /// player. Returns `None` if on the rightmost file or top rank
@@ -278,7 +328,7 @@ impl Square {
     }
     /// Returns an iterator over the squares, rank
     pub fn iterator() -> impl Iterator<Item = Square> {
-        unsafe { (Self::A8 as u8..=Self::H1 as u8).map(|num| std::mem::transmute(num)) }
+        Self::ALL.into_iter()
     }
 }
 impl FromStr for Square {
@@ -313,7 +363,7 @@ impl FromStr for Square {
 }
 
 impl Display for Square {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let file = match self.file() {
             Line::FileA => 'a',
             Line::FileB => 'b',
@@ -354,9 +404,88 @@ mod tests {
         assert_eq!(test.next(), None);
         let test = Square::iterator().last();
         assert_eq!(test, Some(Square::H1));
+        for (i, sq) in Square::iterator().enumerate() {
+            assert_eq!(sq as usize, i);
+        }
+        assert!(Square::iterator().eq(Square::ALL));
     }
     #[test]
     fn display() {
         assert_eq!(Square::H1.to_string(), String::from("h1"));
     }
+    #[test]
+    fn coordinates() {
+        assert_eq!(Square::from_coords(4, 3), Some(Square::E4));
+        assert_eq!(Square::from_coords(0, 0), Some(Square::A1));
+        assert_eq!(Square::from_coords(7, 7), Some(Square::H8));
+        assert_eq!(Square::from_coords(8, 0), None);
+        assert_eq!(Square::from_coords(0, 8), None);
+        for sq in Square::iterator() {
+            assert_eq!(
+                Square::from_coords(sq.file_index(), sq.rank_index()),
+                Some(sq)
+            );
+        }
+        assert_eq!(Square::E4.offset(1, 2), Some(Square::F6));
+        assert_eq!(Square::E4.offset(-4, -3), Some(Square::A1));
+        assert_eq!(Square::A1.offset(-1, 0), None);
+        assert_eq!(Square::H8.offset(0, 1), None);
+        assert_eq!(Square::E4.up(), Some(Square::E5));
+        assert_eq!(Square::A4.left(), None);
+    }
+    #[test]
+    fn indices() {
+        assert_eq!(Square::A8.to_index(), 0);
+        assert_eq!(Square::H1.to_index(), 63);
+        assert_eq!(Square::from_index(64), None);
+        for (i, sq) in Square::iterator().enumerate() {
+            assert_eq!(sq.to_index(), i);
+            assert_eq!(Square::from_index(sq.to_index()), Some(sq));
+        }
+    }
+    #[test]
+    fn between() {
+        assert_eq!(
+            Square::between(Square::A1, Square::A4),
+            [Square::A2, Square::A3]
+        );
+        assert_eq!(
+            Square::between(Square::A1, Square::H8),
+            [
+                Square::B2,
+                Square::C3,
+                Square::D4,
+                Square::E5,
+                Square::F6,
+                Square::G7
+            ]
+        );
+        assert_eq!(
+            Square::between(Square::F1, Square::C1),
+            [Square::E1, Square::D1]
+        );
+        assert!(Square::between(Square::A1, Square::B3).is_empty());
+        assert!(Square::between(Square::A1, Square::B2).is_empty());
+        assert!(Square::between(Square::E4, Square::E4).is_empty());
+    }
+    #[test]
+    fn distances() {
+        assert_eq!(Square::A1.king_distance(Square::H8), 7);
+        assert_eq!(Square::A1.manhattan_distance(Square::H8), 14);
+        assert_eq!(Square::E4.king_distance(Square::F5), 1);
+        assert_eq!(Square::E4.manhattan_distance(Square::E5), 1);
+        assert_eq!(Square::E4.manhattan_distance(Square::F5), 2);
+        assert_eq!(Square::C3.king_distance(Square::C3), 0);
+    }
+    #[test]
+    fn diagonals() {
+        assert!(Square::A1.same_diagonal(Square::H8));
+        assert!(Square::C1.same_diagonal(Square::H6));
+        assert!(!Square::A1.same_anti_diagonal(Square::H8));
+        assert!(Square::A8.same_anti_diagonal(Square::H1));
+        assert!(Square::E4.same_anti_diagonal(Square::B7));
+        assert!(!Square::A1.same_diagonal(Square::H2));
+        assert!(!Square::A1.same_anti_diagonal(Square::H2));
+        assert!(Square::E4.same_diagonal(Square::E4));
+    }
 }
@@ -0,0 +1,50 @@
+use super::square::Square;
+use crate::pieces::{Color, Piece};
+
+const SEED: u64 = 0x5EED_C0FF_EE15_C4E5;
+const PIECE_OFFSET: usize = 0;
+const BLACK_TO_MOVE_OFFSET: usize = PIECE_OFFSET + 12 * 64;
+const CASTLING_OFFSET: usize = BLACK_TO_MOVE_OFFSET + 1;
+const EN_PASSANT_OFFSET: usize = CASTLING_OFFSET + 4;
+const NUM_KEYS: usize = EN_PASSANT_OFFSET + 8;
+
+const KEYS: [u64; NUM_KEYS] = gen_keys();
+
+/// Generates the key table at compile time with the splitmix64 generator so that hashes are
+/// stable between runs
+const fn gen_keys() -> [u64; NUM_KEYS] {
+    let mut keys = [0; NUM_KEYS];
+    let mut state = SEED;
+    let mut i = 0;
+    while i < NUM_KEYS {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+pub(super) fn piece_key(sq: Square, piece: Piece) -> u64 {
+    let color_offset = match piece.color {
+        Color::White => 0,
+        Color::Black => 6,
+    };
+    let piece_index = piece.piece.to_index() + color_offset;
+    KEYS[PIECE_OFFSET + piece_index * 64 + sq.to_index()]
+}
+
+pub(super) fn black_to_move_key() -> u64 {
+    KEYS[BLACK_TO_MOVE_OFFSET]
+}
+
+/// The castling rights are indexed in fen order, `KQkq`
+pub(super) fn castling_key(index: usize) -> u64 {
+    KEYS[CASTLING_OFFSET + index]
+}
+
+pub(super) fn en_passant_key(sq: Square) -> u64 {
+    KEYS[EN_PASSANT_OFFSET + sq.to_index() % 8]
+}
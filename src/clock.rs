@@ -0,0 +1,70 @@
+use std::time::{Duration, Instant};
+
+use crate::pieces::Color;
+
+#[derive(Debug, Clone, Copy)]
+/// A chess clock that tracks the remaining time of each player, with an increment added after
+/// every move
+pub struct Clock {
+    initial: Duration,
+    increment: Duration,
+    white_remaining: Duration,
+    black_remaining: Duration,
+    white_to_move: bool,
+    turn_start: Instant,
+}
+
+impl Clock {
+    /// Creates a new clock with the initial time for each player, and the increment that is added
+    /// after each move. White's time starts running immediately
+    pub fn new(initial: Duration, increment: Duration) -> Clock {
+        Clock {
+            initial,
+            increment,
+            white_remaining: initial,
+            black_remaining: initial,
+            white_to_move: true,
+            turn_start: Instant::now(),
+        }
+    }
+    /// Returns the remaining time of the given player, including the time spent on the current
+    /// turn if it is that player's move
+    pub fn time_remaining(&self, color: impl Into<Color>) -> Duration {
+        let is_white = color.into().is_white();
+        let remaining = if is_white {
+            self.white_remaining
+        } else {
+            self.black_remaining
+        };
+        if is_white == self.white_to_move {
+            remaining.saturating_sub(self.turn_start.elapsed())
+        } else {
+            remaining
+        }
+    }
+    /// Returns whether the given player has run out of time
+    pub fn is_flagged(&self, color: impl Into<Color>) -> bool {
+        self.time_remaining(color).is_zero()
+    }
+    /// Ends the current player's turn, deducting the time spent and adding the increment, then
+    /// starts the other player's time
+    ///
+    /// The increment is not added if the player has already run out of time
+    pub fn press(&mut self) {
+        let mut remaining = self.time_remaining(self.white_to_move);
+        if !remaining.is_zero() {
+            remaining += self.increment;
+        }
+        if self.white_to_move {
+            self.white_remaining = remaining;
+        } else {
+            self.black_remaining = remaining;
+        }
+        self.white_to_move = !self.white_to_move;
+        self.turn_start = Instant::now();
+    }
+    /// Resets both players to the initial time, with white to move
+    pub fn reset(&mut self) {
+        *self = Clock::new(self.initial, self.increment);
+    }
+}
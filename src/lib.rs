@@ -1,12 +1,25 @@
 #![warn(missing_docs)]
+#![cfg_attr(not(feature = "std"), no_std)]
 //! Chess board representation
 //!
 //! Provides a board representation to create a chess game
+//!
+//! Without the default `std` feature, the crate is `no_std` and only needs `alloc`. The board,
+//! move generation, and parsing are still available, but [ChessGame], the clock, and pgn reading
+//! are not
+extern crate alloc;
+
+/// Module that picks moves for a computer opponent
+pub mod ai;
 /// Module that concerns the board state
 pub mod board;
+/// Module that concerns the game clocks
+#[cfg(feature = "std")]
+pub mod clock;
 /// Module that parses algebraic chess notation into a [Turn]
 pub mod parser;
 /// Module for dealing with pgn files
+#[cfg(feature = "std")]
 pub mod pgn;
 /// Module that concerns the pieces
 pub mod pieces;
@@ -15,32 +28,64 @@ pub mod turn;
 /// Utility structs and functions for miscellaneous tasks
 pub mod utils;
 
+use core::fmt::{self, Display};
+#[cfg(feature = "std")]
 use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::time::Duration;
 
-use board::{ChessBoard, DrawType, GameState, TurnError, Win, WinType};
+use board::TurnError;
+#[cfg(feature = "std")]
+use board::{ChessBoard, DrawType, GameState, Outcome, Source, Square, UndoRecord, Win, WinType};
+#[cfg(feature = "std")]
+use clock::Clock;
+use parser::ChessParseError;
+#[cfg(feature = "std")]
+use pieces::Color;
+#[cfg(feature = "std")]
 use turn::Turn;
 
+#[cfg(feature = "std")]
 use utils::Counter;
 
+#[cfg(feature = "std")]
 #[derive(Debug, Clone)]
 /// Structure that holds the chess board, game history, and configuration data
 pub struct ChessGame {
     board: ChessBoard,
+    start_board: ChessBoard,
     /// The current game state [GameState]
     pub game_state: GameState,
-    position_counter: Counter<String>,
+    position_counter: Counter<u64>,
     game_hist: Vec<Turn>,
+    undo_stack: Vec<UndoRecord>,
+    redo_stack: Vec<Turn>,
+    clock: Option<Clock>,
+    clock_hist: Vec<Duration>,
+    draw_offer: Option<(Color, usize)>,
     /// Sets the perspective that the game is played from, White, Black, or switching between them
     pub rotate_board: RotateBoard,
     /// Sets whether move undos are allowed
     pub allow_undo: bool,
+    /// Sets whether null moves can be made to pass the turn, as used in analysis
+    pub allow_null_moves: bool,
     /// The metadata about the game, stored in the square brackets in the pgn file
     pub game_info: HashMap<String, String>,
-    /// Sets whether or not the check `+`, capture `x`, and checkmate `#` flags must be specified or
-    /// will be autogenerated for the user input
-    pub enforce_flags: bool,
+    /// Sets whether the check `+`, capture `x`, and checkmate `#` flags of the user input must be
+    /// specified, are checked only when given, or are ignored and generated
+    pub flag_policy: FlagPolicy,
+    /// Sets whether threefold repetition and the fifty move rule end the game automatically,
+    /// rather than needing to be claimed with [ChessGame::claim_draw]
+    pub auto_draw: bool,
+    /// Sets whether en passant captures are followed by `e.p.` in the generated pgn, as in
+    /// `exd6 e.p.`
+    pub en_passant_suffix: bool,
+    /// Sets whether each move in the generated pgn is followed by a `[%clk]` comment with the
+    /// mover's remaining time, when the game has a clock
+    pub pgn_clock_comments: bool,
 }
 
+#[cfg(feature = "std")]
 impl ChessGame {
     /// associated function to make a builder for configuration data
     pub fn builder() -> ChessGameBuilder {
@@ -51,76 +96,130 @@ impl ChessGame {
         self.board.gen_fen()
     }
     /// generates a pgn string for the current game history
+    ///
+    /// The header contains the seven tag roster, taken from game_info, with unknown values
+    /// written as `?`, followed by the `SetUp` and `FEN` tags if the game didn't start from the
+    /// standard position, and the rest of the tags in game_info in alphabetical order
+    ///
+    /// If the game is still in progress but a draw can be claimed, a comment naming the draw is
+    /// written before the result
+    ///
+    /// If pgn_clock_comments is set on a timed game, each move is followed by a `{[%clk H:MM:SS]}`
+    /// comment with the time the mover had left after the move
     pub fn gen_pgn(&self) -> String {
         let mut contents = String::new();
-        let result = match self.board.check_gamestate(&self.position_counter) {
-            GameState::Continue | GameState::Stop => "*",
-            GameState::Win(Win { is_white: true, .. }) => "1-0",
-            GameState::Win(Win {
-                is_white: false, ..
-            }) => "0-1",
-            GameState::Draw(_) => "1/2-1/2",
-        };
-        let mut test_board = ChessBoard::default();
-        for (turn_num, moves) in self.game_hist.chunks(2).enumerate() {
-            contents.push_str(&format!("{}. ", turn_num + 1));
-            for r#move in moves {
-                let minimum_move = test_board.get_minimum_move(r#move);
-                test_board.update_board(r#move);
-                contents.push_str(&minimum_move.to_string());
-                contents.push(' ');
+        let result = self.result_token();
+        let seven_tag_roster = [
+            ("Event", "?"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", "?"),
+            ("White", "?"),
+            ("Black", "?"),
+        ];
+        for (tag, default) in seven_tag_roster {
+            let value = self.game_info.get(tag).map_or(default, |x| x.as_ref());
+            contents.push_str(&pgn_tag(tag, value));
+        }
+        contents.push_str(&pgn_tag("Result", result));
+        if self.start_board != ChessBoard::default() {
+            contents.push_str(&pgn_tag("SetUp", "1"));
+            contents.push_str(&pgn_tag("FEN", &self.start_board.gen_fen()));
+        }
+        let mut other_tags = self
+            .game_info
+            .iter()
+            .filter(|(tag, _)| {
+                !seven_tag_roster
+                    .iter()
+                    .any(|(roster_tag, _)| roster_tag == tag)
+                    && !matches!(tag.as_str(), "Result" | "SetUp" | "FEN")
+            })
+            .collect::<Vec<_>>();
+        other_tags.sort();
+        for (tag, value) in other_tags {
+            contents.push_str(&pgn_tag(tag, value));
+        }
+        contents.push('\n');
+
+        // each move number is kept on the same line as white's move
+        let mut tokens = Vec::new();
+        let mut test_board = self.start_board.clone();
+        for (i, r#move) in self.game_hist.iter().enumerate() {
+            let san = test_board.get_minimum_move(r#move).to_string();
+            let move_number = test_board.full_move_number();
+            if test_board.side_to_move() == Color::White {
+                tokens.push(format!("{move_number}. {san}"));
+            } else if i == 0 {
+                tokens.push(format!("{move_number}... {san}"));
+            } else {
+                tokens.push(san);
+            }
+            // written as its own token so that it follows any check or checkmate flag
+            if self.en_passant_suffix && test_board.is_en_passant(r#move) {
+                tokens.push(String::from("e.p."));
             }
-            if turn_num % 10 == 9 {
-                contents.push('\n');
+            if let Some(remaining) = self.clock_hist.get(i).filter(|_| self.pgn_clock_comments) {
+                tokens.push(format!("{{[%clk {}]}}", pgn_clock(*remaining)));
             }
+            test_board.update_board(r#move);
+        }
+        if let Some(draw) = self.can_claim_draw() {
+            tokens.push(format!("{{Draw claimable by {draw}}}"));
         }
-        contents.push_str(result);
+        tokens.push(result.to_owned());
+        contents.push_str(&wrap_pgn_tokens(&tokens));
         contents
     }
+    /// Sets a tag in the game info, which is written in the header of the generated pgn
+    pub fn set_pgn_tag(&mut self, tag: &str, value: &str) {
+        self.game_info.insert(tag.to_owned(), value.to_owned());
+    }
     /// resets the state of the board, without resetting the configuration
     pub fn reset(&mut self) {
         self.board = ChessBoard::default();
+        self.start_board = ChessBoard::default();
         self.game_state = GameState::default();
         self.position_counter = Counter::default();
         self.game_hist = Vec::default();
+        self.undo_stack = Vec::default();
+        self.redo_stack = Vec::default();
+        self.clock_hist = Vec::default();
+        self.draw_offer = None;
+        if let Some(clock) = self.clock.as_mut() {
+            clock.reset();
+        }
     }
-    /// Displays the ending message describing the type of win, prints nothing if the game is ongoing
-    pub fn display_end_message(&self) {
+    /// Replaces the position with the one described by the fen string, starting the game over from
+    /// it without resetting the configuration
+    ///
+    /// # Side effects
+    ///
+    /// On success, clears the game history and resets the game state for the new position
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the fen string is invalid, in which case the game is unchanged
+    pub fn set_position(&mut self, fen: &str) -> Result<(), &'static str> {
+        let board = fen.parse::<ChessBoard>()?;
+        self.reset();
+        self.start_board = board.clone();
+        self.board = board;
+        self.update_game_state();
+        Ok(())
+    }
+    /// Returns the message describing how the game ended, naming the winner with
+    /// [ChessGame::player_name], or `None` if the game is ongoing
+    pub fn end_message(&self) -> Option<String> {
         match self.game_state {
-            GameState::Win(win) => {
-                match win.is_white {
-                    true => print!(
-                        "{} wins by ",
-                        self.game_info
-                            .get("White")
-                            .unwrap_or(&String::from("White"))
-                    ),
-                    false => print!(
-                        "{} wins by ",
-                        self.game_info
-                            .get("Black")
-                            .unwrap_or(&String::from("Black"))
-                    ),
-                }
-                match win.kind {
-                    WinType::Checkmate => println!("checkmate"),
-                    WinType::Resign => println!("resignation"),
-                    WinType::Timeout => println!("timeout"),
-                }
-            }
-            GameState::Draw(draw) => {
-                print!("The game is a draw by ");
-                match draw {
-                    DrawType::Stalemate => println!("stalemate"),
-                    DrawType::FiftyMove => println!("the fifty move rule"),
-                    DrawType::ThreefoldRepitition => println!("threefold repitition"),
-                    DrawType::InsufficientMaterial => println!("insufficient material"),
-                    DrawType::Offer => println!("draw offer"),
-                }
-            }
-            GameState::Stop => println!("The game was aborted"),
-            GameState::Continue => (),
-        };
+            GameState::Continue => None,
+            GameState::Win(win) => Some(format!(
+                "{} wins by {}",
+                self.player_name(win.is_white),
+                win.kind
+            )),
+            state => Some(state.to_string()),
+        }
     }
     /// Makes a move based on the inputted [Turn]
     ///
@@ -131,35 +230,202 @@ impl ChessGame {
     ///
     /// # Errors
     ///
-    /// Returns an error if a move is not a legal chess move. If the enforce flags field is true,
-    /// then will also return an error if the flags are incorrect
+    /// Returns an error if a move is not a legal chess move, or if it is a null move and the
+    /// allow_null_moves flag is false. Depending on the flag_policy field, will also return an
+    /// error if the flags are incorrect
     pub fn make_move(&mut self, turn: &Turn) -> Result<(), TurnError> {
+        if *turn == Turn::Null && !self.allow_null_moves {
+            return Err(TurnError::NullMoveNotAllowed);
+        }
         let full_turn = self.board.validate_and_complete_turn(*turn)?;
         if let Turn::Move(r#move) = full_turn {
             let Some(board::Source::Square(_)) = r#move.src else {
                 panic!("Invalid output from validate_and_complete_turn");
             };
         }
-        let full_turn = if self.enforce_flags {
-            self.board.enforce_flags(&full_turn)?;
-            full_turn
-        } else {
-            self.board.gen_flags(full_turn)
+        let full_turn = match self.flag_policy {
+            FlagPolicy::Enforce => {
+                self.board.enforce_flags(&full_turn)?;
+                full_turn
+            }
+            FlagPolicy::Lenient => {
+                self.board.validate_given_flags(&full_turn)?;
+                self.board.gen_flags(full_turn)
+            }
+            FlagPolicy::Generate => self.board.gen_flags(full_turn),
         };
-        let trimmed_fen = self
-            .board
-            .gen_fen()
-            .split_whitespace()
-            .take(4)
-            .collect::<Vec<_>>()
-            .join(" ");
-        self.position_counter.add(trimmed_fen);
-        self.board.update_board(&full_turn);
+        let color = self.board.side_to_move();
+        // the offer expires once the player who made it moves again after their offer
+        if self
+            .draw_offer
+            .is_some_and(|(offered_by, ply)| offered_by == color && self.game_hist.len() > ply)
+        {
+            self.draw_offer = None;
+        }
+        self.apply_turn(full_turn);
+        self.redo_stack.clear();
+        if let Some(clock) = self.clock.as_mut() {
+            clock.press();
+            self.clock_hist.push(clock.time_remaining(color));
+            if clock.is_flagged(color) {
+                self.game_state = GameState::Win(Win {
+                    is_white: color.opposite().is_white(),
+                    kind: WinType::Timeout,
+                });
+            }
+        }
+        Ok(())
+    }
+    /// Parses a move in algebraic notation and makes it, the same as [ChessGame::make_move]
+    ///
+    /// # Side effects
+    ///
+    /// On success, updates the game the same as [ChessGame::make_move]
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::Parse] if the move can't be parsed, and [Error::Turn] if
+    /// [ChessGame::make_move] rejects it
+    pub fn make_move_san(&mut self, san: &str) -> Result<(), Error> {
+        let turn = san.parse::<Turn>()?;
+        self.make_move(&turn)?;
+        Ok(())
+    }
+    /// Parses and makes each move in order, stopping at the first move that fails
+    ///
+    /// # Side effects
+    ///
+    /// Every move before the failing one is made, as if by [ChessGame::make_move]
+    ///
+    /// # Errors
+    ///
+    /// Returns the index of the first move that couldn't be parsed or made, along with the error
+    pub fn play_moves(&mut self, moves: &[&str]) -> Result<(), (usize, TurnError)> {
+        self.play_all(
+            moves
+                .iter()
+                .map(|r#move| r#move.parse().map_err(TurnError::InvalidNotation)),
+        )
+    }
+    /// Makes each turn in order, stopping at the first turn that fails
+    ///
+    /// # Side effects
+    ///
+    /// Every turn before the failing one is made, as if by [ChessGame::make_move]
+    ///
+    /// # Errors
+    ///
+    /// Returns the index of the first turn that couldn't be made, along with the error
+    pub fn play_turns(&mut self, turns: &[Turn]) -> Result<(), (usize, TurnError)> {
+        self.play_all(turns.iter().copied().map(Ok))
+    }
+    fn play_all(
+        &mut self,
+        turns: impl IntoIterator<Item = Result<Turn, TurnError>>,
+    ) -> Result<(), (usize, TurnError)> {
+        for (idx, turn) in turns.into_iter().enumerate() {
+            turn.and_then(|turn| self.make_move(&turn))
+                .map_err(|e| (idx, e))?;
+        }
+        Ok(())
+    }
+    fn apply_turn(&mut self, full_turn: Turn) {
+        let position_hash = self.board.zobrist_hash();
+        let token = self.board.make_unchecked(&full_turn);
+        self.position_counter.add(position_hash);
         self.game_hist.push(full_turn);
+        self.undo_stack.push(UndoRecord {
+            token,
+            position_hash,
+        });
 
+        self.update_game_state();
+    }
+    fn update_game_state(&mut self) {
         self.game_state = self.board.check_gamestate(&self.position_counter);
+        if self.auto_draw {
+            if let Some(draw) = self.can_claim_draw() {
+                self.game_state = GameState::Draw(draw);
+            }
+        }
+    }
+    /// Returns the draw that the current player can claim, or `None` if no draw can be claimed or
+    /// the game is already over
+    pub fn can_claim_draw(&self) -> Option<DrawType> {
+        if self.game_state != GameState::Continue {
+            return None;
+        }
+        self.board.claimable_draw(&self.position_counter)
+    }
+    /// Claims a draw by threefold repetition or the fifty move rule
+    ///
+    /// # Side effects
+    ///
+    /// On success, sets the game state to the claimed draw
+    ///
+    /// # Errors
+    ///
+    /// Returns [DrawOfferError::NoClaimableDraw] if there is no draw that can be claimed
+    pub fn claim_draw(&mut self) -> Result<(), DrawOfferError> {
+        let draw = self
+            .can_claim_draw()
+            .ok_or(DrawOfferError::NoClaimableDraw)?;
+        self.game_state = GameState::Draw(draw);
+        Ok(())
+    }
+    /// Resigns the game for the current player
+    ///
+    /// # Side effects
+    ///
+    /// Sets the game state to a win by resignation for the other player
+    pub fn resign(&mut self) {
+        self.game_state = GameState::Win(Win {
+            is_white: self.side_to_move().opposite().is_white(),
+            kind: WinType::Resign,
+        });
+    }
+    /// Offers a draw from the current player, which the other player can accept with
+    /// [ChessGame::accept_draw] or decline with [ChessGame::decline_draw]
+    ///
+    /// The offer stands while the current player makes their move, and expires when they make
+    /// another move
+    pub fn offer_draw(&mut self) {
+        self.draw_offer = Some((self.side_to_move(), self.game_hist.len()));
+    }
+    /// Returns the player who offered a draw, or `None` if no draw offer is pending
+    pub fn draw_offered_by(&self) -> Option<Color> {
+        self.draw_offer.map(|(color, _)| color)
+    }
+    /// Accepts the pending draw offer for the current player
+    ///
+    /// # Side effects
+    ///
+    /// On success, sets the game state to a draw by agreement
+    ///
+    /// # Errors
+    ///
+    /// Returns [DrawOfferError::NoOffer] if no draw has been offered, or
+    /// [DrawOfferError::OwnOffer] if the offer was made by the current player
+    pub fn accept_draw(&mut self) -> Result<(), DrawOfferError> {
+        match self.draw_offered_by() {
+            None => return Err(DrawOfferError::NoOffer),
+            Some(color) if color == self.side_to_move() => return Err(DrawOfferError::OwnOffer),
+            Some(_) => self.draw_offer = None,
+        }
+        self.game_state = GameState::Draw(DrawType::Agreement);
         Ok(())
     }
+    /// Declines the pending draw offer, and the game continues
+    ///
+    /// # Errors
+    ///
+    /// Returns [DrawOfferError::NoOffer] if no draw has been offered
+    pub fn decline_draw(&mut self) -> Result<(), DrawOfferError> {
+        self.draw_offer
+            .take()
+            .map(|_| ())
+            .ok_or(DrawOfferError::NoOffer)
+    }
     /// Undoes the last move if the allow_undo flag is set
     ///
     /// # Side effects
@@ -173,65 +439,186 @@ impl ChessGame {
         if !self.allow_undo {
             return None;
         }
-        self.game_hist.pop();
-        let history = self.game_hist.clone();
-
-        self.game_hist = Vec::new();
-        self.position_counter = Counter::new();
-        self.board = ChessBoard::default();
-        for turn in history {
-            self.make_move(&turn).unwrap();
+        let (Some(turn), Some(undo_record)) = (self.game_hist.pop(), self.undo_stack.pop()) else {
+            return Some(());
+        };
+        self.board.unmake(undo_record.token);
+        self.redo_stack.push(turn);
+        self.clock_hist.truncate(self.game_hist.len());
+        self.position_counter.remove(&undo_record.position_hash);
+        self.update_game_state();
+        Some(())
+    }
+    /// Redoes the last undone move if the allow_undo flag is set
+    ///
+    /// # Side effects
+    ///
+    /// On success, makes the last undone move again. Making a new move clears the moves that can
+    /// be redone
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the allow_undo flag is false or if there is no move to redo
+    pub fn redo_move(&mut self) -> Option<()> {
+        if !self.allow_undo {
+            return None;
+        }
+        let turn = self.redo_stack.pop()?;
+        let color = self.board.side_to_move();
+        self.apply_turn(turn);
+        // the clock isn't pressed for a redone move, so the mover's current time is recorded
+        if let Some(clock) = self.clock {
+            self.clock_hist.push(clock.time_remaining(color));
         }
         Some(())
     }
-    /// Displays the visual state of the board, depending on the perspective set in rotate_board
-    pub fn display(&self) {
-        const ED0: &str = "\x1b[J";
-        const CUP: &str = "\x1b[H";
-        let board = match self.rotate_board {
-            RotateBoard::White => format!("{}", self.board),
-            RotateBoard::Black => format!("{:#}", self.board),
-            RotateBoard::Rotate if self.is_white() => format!("{}", self.board),
-            RotateBoard::Rotate => format!("{:#}", self.board),
-        };
-        print!("{}", CUP);
-        print!("{}", ED0);
-        print!("{}", board);
-        let curr_player = if self.board.is_white() {
-            self.game_info.get("White").map_or("White", |x| x.as_ref())
-        } else {
-            self.game_info.get("Black").map_or("Black", |x| x.as_ref())
-        };
-        println!("{curr_player}'s turn");
+    /// Returns whether [ChessGame::undo_move] would undo a move, which needs the allow_undo flag
+    /// and a move to undo
+    pub fn can_undo(&self) -> bool {
+        self.allow_undo && !self.game_hist.is_empty()
+    }
+    /// Returns whether [ChessGame::redo_move] would redo a move, which needs the allow_undo flag
+    /// and an undone move
+    pub fn can_redo(&self) -> bool {
+        self.allow_undo && !self.redo_stack.is_empty()
+    }
+    /// Returns the number of half moves played in this game, not counting any moves before the
+    /// starting position
+    pub fn ply_count(&self) -> usize {
+        self.game_hist.len()
+    }
+    /// Returns the remaining time of the given player, returns `None` if the game is untimed
+    pub fn time_remaining(&self, color: impl Into<Color>) -> Option<Duration> {
+        let color = color.into();
+        self.clock.map(|clock| clock.time_remaining(color))
+    }
+    /// Ends the game by timeout if the current player has run out of time
+    ///
+    /// # Side effects
+    ///
+    /// Sets the game state to a win by timeout for the other player if the current player's
+    /// time has run out
+    pub fn flag(&mut self) {
+        let color = self.board.side_to_move();
+        if self.clock.is_some_and(|clock| clock.is_flagged(color)) {
+            self.game_state = GameState::Win(Win {
+                is_white: color.opposite().is_white(),
+                kind: WinType::Timeout,
+            });
+        }
     }
     /// Returns the string that represents the visual state of the board, depending on the
     /// perspective set in rotate_board
     pub fn board_string(&self) -> String {
-        match self.rotate_board {
+        self.board_ascii(self.rotate_board)
+    }
+    /// Returns the string that represents the visual state of the board from the given
+    /// perspective, ignoring rotate_board. Contains no terminal escape codes
+    pub fn board_ascii(&self, perspective: RotateBoard) -> String {
+        match perspective {
             RotateBoard::White => format!("{}", self.board),
             RotateBoard::Black => format!("{:#}", self.board),
-            RotateBoard::Rotate if self.is_white() => format!("{}", self.board),
+            RotateBoard::Rotate if self.side_to_move() == Color::White => format!("{}", self.board),
             RotateBoard::Rotate => format!("{:#}", self.board),
         }
     }
     /// Returns the string that says which player's turn it is
     pub fn player_string(&self) -> String {
-        if self.board.is_white() {
-            self.game_info.get("White").map_or("White", |x| x.as_ref())
-        } else {
-            self.game_info.get("Black").map_or("Black", |x| x.as_ref())
+        self.player_name(self.board.side_to_move()).to_owned() + "'s turn"
+    }
+    /// Returns the name of the given player, stored in the `White` and `Black` tags of
+    /// game_info, defaulting to "White" and "Black"
+    pub fn player_name(&self, color: impl Into<Color>) -> &str {
+        match color.into() {
+            Color::White => self.game_info.get("White").map_or("White", |x| x.as_ref()),
+            Color::Black => self.game_info.get("Black").map_or("Black", |x| x.as_ref()),
         }
-        .to_owned()
-            + "'s turn"
+    }
+    /// Returns who won the game and how, or `None` while the game is still in play
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.game_state.outcome()
+    }
+    /// Returns the pgn result token of the game, `1-0`, `0-1`, or `1/2-1/2`, or `*` if the game
+    /// is still in progress or was stopped
+    pub fn result_token(&self) -> &'static str {
+        self.outcome()
+            .map_or("*", |outcome| outcome.pgn_result_token())
+    }
+    /// Returns the player whose turn it is
+    pub fn side_to_move(&self) -> Color {
+        self.board.side_to_move()
     }
     /// Returns true if the current player is white, false if it is black
+    #[deprecated(note = "use `ChessGame::side_to_move` instead")]
     pub fn is_white(&self) -> bool {
-        self.board.is_white()
+        self.side_to_move().is_white()
+    }
+    /// Returns true if the flag_policy is [FlagPolicy::Enforce], the old `enforce_flags` setting
+    #[deprecated(note = "use the `ChessGame::flag_policy` field instead")]
+    pub fn enforce_flags(&self) -> bool {
+        self.flag_policy == FlagPolicy::Enforce
+    }
+    /// Returns the last move played, returns `None` if no move has been played
+    pub fn last_move(&self) -> Option<&Turn> {
+        self.game_hist.last()
+    }
+    /// Returns the source and destination squares of the last move played, so both can be
+    /// highlighted. For castling, these are the squares of the king
+    pub fn last_move_squares(&self) -> Option<(Square, Square)> {
+        match self.last_move()? {
+            Turn::Castling(castling_type, _) => {
+                let (king_squares, _) = self
+                    .board
+                    .castling_squares(self.side_to_move().opposite(), castling_type);
+                Some(king_squares)
+            }
+            Turn::Move(r#move) => match r#move.src {
+                Some(Source::Square(src)) => Some((src, r#move.dst)),
+                _ => None,
+            },
+            Turn::Null => None,
+        }
     }
     /// Returns a reference to the game history
     pub fn game_hist(&self) -> &Vec<Turn> {
         &self.game_hist
     }
+    /// Returns the board after the first `ply` half moves of the game, returns `None` if fewer
+    /// half moves have been played
+    pub fn board_at_ply(&self, ply: usize) -> Option<ChessBoard> {
+        let mut board = self.start_board.clone();
+        for turn in self.game_hist.get(..ply)? {
+            board.update_board(turn);
+        }
+        Some(board)
+    }
+    /// Returns an iterator over the game history, yielding the ply, the turn, and the board after
+    /// that turn is made, so the board yielded with `ply` matches [ChessGame::board_at_ply]
+    ///
+    /// A single board is updated by one move per step, rather than replaying from the start
+    pub fn replay(&self) -> impl Iterator<Item = (usize, Turn, ChessBoard)> + '_ {
+        self.game_hist
+            .iter()
+            .enumerate()
+            .scan(self.start_board.clone(), |board, (i, turn)| {
+                board.update_board(turn);
+                Some((i + 1, *turn, board.clone()))
+            })
+    }
+    /// Returns the fen string of every position in the game, starting with the initial position
+    /// and followed by the position after each move
+    pub fn fen_history(&self) -> Vec<String> {
+        std::iter::once(self.start_board.gen_fen())
+            .chain(
+                self.game_hist
+                    .iter()
+                    .scan(self.start_board.clone(), |board, turn| {
+                        board.update_board(turn);
+                        Some(board.gen_fen())
+                    }),
+            )
+            .collect()
+    }
     /// Returns the turn with the least amount of information to fully specify a move, given a
     /// fully qualified move.
     ///
@@ -247,6 +634,43 @@ impl ChessGame {
     }
 }
 
+/// The maximum length of a line of pgn movetext
+#[cfg(feature = "std")]
+const PGN_LINE_WIDTH: usize = 80;
+
+/// Joins the movetext tokens with spaces, starting a new line instead whenever the current line
+/// would go over [PGN_LINE_WIDTH]
+#[cfg(feature = "std")]
+fn wrap_pgn_tokens(tokens: &[String]) -> String {
+    let mut movetext = String::new();
+    let mut line_len = 0;
+    for token in tokens {
+        if line_len > 0 && line_len + 1 + token.len() > PGN_LINE_WIDTH {
+            movetext.push('\n');
+            line_len = 0;
+        } else if line_len > 0 {
+            movetext.push(' ');
+            line_len += 1;
+        }
+        movetext.push_str(token);
+        line_len += token.len();
+    }
+    movetext
+}
+
+/// Formats the remaining time as `H:MM:SS` for a pgn `[%clk]` comment
+#[cfg(feature = "std")]
+fn pgn_clock(remaining: Duration) -> String {
+    let secs = remaining.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+#[cfg(feature = "std")]
+fn pgn_tag(tag: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("[{tag} \"{value}\"]\n")
+}
+
 #[derive(PartialEq, Debug, Clone, Copy)]
 /// Enum that specifies the orientation of the board when displayed
 pub enum RotateBoard {
@@ -258,38 +682,152 @@ pub enum RotateBoard {
     Rotate,
 }
 
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+/// Enum that specifies how the check `+`, capture `x`, and checkmate `#` flags of a move are
+/// treated
+pub enum FlagPolicy {
+    /// The flags must be given exactly, a missing or extra flag is an error
+    #[default]
+    Enforce,
+    /// The flags are ignored and generated from the position
+    Generate,
+    /// The flags that are given must be correct, but missing flags are generated
+    Lenient,
+}
+
+impl From<bool> for FlagPolicy {
+    /// Converts `true` to [FlagPolicy::Enforce] and `false` to [FlagPolicy::Generate], the two
+    /// behaviors of the old `enforce_flags` setting
+    fn from(enforce_flags: bool) -> Self {
+        if enforce_flags {
+            FlagPolicy::Enforce
+        } else {
+            FlagPolicy::Generate
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Error type covering the ways that reading and playing a move can fail
+pub enum Error {
+    /// The move couldn't be parsed
+    Parse(ChessParseError),
+    /// The move was parsed but isn't legal in the position
+    Turn(TurnError),
+}
+
+impl From<ChessParseError> for Error {
+    fn from(err: ChessParseError) -> Self {
+        Error::Parse(err)
+    }
+}
+
+impl From<TurnError> for Error {
+    fn from(err: TurnError) -> Self {
+        Error::Turn(err)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(err) => write!(f, "{err}"),
+            Error::Turn(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        // the wrapped error is displayed directly, so the chain continues from its source
+        match self {
+            Error::Parse(err) => err.source(),
+            Error::Turn(err) => err.source(),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+/// Error type for answering a draw offer or claiming a draw
+pub enum DrawOfferError {
+    /// There is no pending draw offer to answer
+    NoOffer,
+    /// The pending draw offer was made by the player trying to accept it
+    OwnOffer,
+    /// There is no threefold repetition or fifty move draw to claim
+    NoClaimableDraw,
+}
+
+impl Display for DrawOfferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrawOfferError::NoOffer => write!(f, "There is no draw offer"),
+            DrawOfferError::OwnOffer => write!(f, "A player can't accept their own draw offer"),
+            DrawOfferError::NoClaimableDraw => write!(f, "There is no draw to claim"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DrawOfferError {}
+
+#[cfg(feature = "std")]
 impl Default for ChessGame {
     fn default() -> Self {
         ChessGame {
             board: ChessBoard::default(),
+            start_board: ChessBoard::default(),
             game_state: GameState::default(),
             position_counter: Counter::new(),
             game_hist: Vec::default(),
+            undo_stack: Vec::default(),
+            redo_stack: Vec::default(),
+            clock: None,
+            clock_hist: Vec::default(),
+            draw_offer: None,
             rotate_board: RotateBoard::White,
             allow_undo: false,
+            allow_null_moves: false,
             game_info: HashMap::default(),
-            enforce_flags: true,
+            flag_policy: FlagPolicy::Enforce,
+            auto_draw: false,
+            en_passant_suffix: false,
+            pgn_clock_comments: false,
         }
     }
 }
 
+#[cfg(feature = "std")]
 /// builder struct for setting configuration on a ChessGame
 pub struct ChessGameBuilder {
     rotate_board: RotateBoard,
     allow_undo: bool,
+    allow_null_moves: bool,
     game_info: HashMap<String, String>,
-    enforce_flags: bool,
+    flag_policy: FlagPolicy,
+    auto_draw: bool,
+    en_passant_suffix: bool,
+    pgn_clock_comments: bool,
+    clock: Option<Clock>,
 }
+#[cfg(feature = "std")]
 impl Default for ChessGameBuilder {
     fn default() -> Self {
         ChessGameBuilder {
             rotate_board: RotateBoard::White,
             allow_undo: false,
+            allow_null_moves: false,
             game_info: HashMap::new(),
-            enforce_flags: true,
+            flag_policy: FlagPolicy::Enforce,
+            auto_draw: false,
+            en_passant_suffix: false,
+            pgn_clock_comments: false,
+            clock: None,
         }
     }
 }
+#[cfg(feature = "std")]
 impl ChessGameBuilder {
     /// initializes a ChessGameBuilder
     pub fn new() -> Self {
@@ -316,6 +854,16 @@ impl ChessGameBuilder {
         self
     }
 
+    /// Sets the allow_null_moves flag
+    ///
+    /// # Default
+    ///
+    /// `false`
+    pub fn allow_null_moves(&mut self, val: bool) -> &mut Self {
+        self.allow_null_moves = val;
+        self
+    }
+
     /// Sets the player names, white first, then black
     ///
     /// # Default
@@ -327,13 +875,62 @@ impl ChessGameBuilder {
         self
     }
 
-    /// Sets the enforce_flags field
+    /// Sets the flag_policy field
     ///
     /// # Default
     ///
-    /// `true`
+    /// [FlagPolicy::Enforce]
+    pub fn flag_policy(&mut self, val: FlagPolicy) -> &mut Self {
+        self.flag_policy = val;
+        self
+    }
+
+    /// Sets the flag_policy field to [FlagPolicy::Enforce] when `true` and to
+    /// [FlagPolicy::Generate] when `false`
+    #[deprecated(note = "use `ChessGameBuilder::flag_policy` instead")]
     pub fn enforce_flags(&mut self, val: bool) -> &mut Self {
-        self.enforce_flags = val;
+        self.flag_policy = val.into();
+        self
+    }
+
+    /// Sets the auto_draw field
+    ///
+    /// # Default
+    ///
+    /// `false`
+    pub fn auto_draw(&mut self, val: bool) -> &mut Self {
+        self.auto_draw = val;
+        self
+    }
+
+    /// Sets the en_passant_suffix field
+    ///
+    /// # Default
+    ///
+    /// `false`
+    pub fn en_passant_suffix(&mut self, val: bool) -> &mut Self {
+        self.en_passant_suffix = val;
+        self
+    }
+
+    /// Sets the pgn_clock_comments field
+    ///
+    /// # Default
+    ///
+    /// `false`
+    pub fn pgn_clock_comments(&mut self, val: bool) -> &mut Self {
+        self.pgn_clock_comments = val;
+        self
+    }
+
+    /// Sets the game clock, with the initial time for each player and the increment added after
+    /// each move
+    ///
+    /// # Default
+    ///
+    /// No clock, the game is untimed
+    pub fn clock(&mut self, initial: Duration, increment: Duration) -> &mut Self {
+        self.clock = Some(Clock::new(initial, increment));
         self
     }
 
@@ -342,9 +939,659 @@ impl ChessGameBuilder {
         ChessGame {
             rotate_board: self.rotate_board,
             allow_undo: self.allow_undo,
+            allow_null_moves: self.allow_null_moves,
             game_info: self.game_info,
-            enforce_flags: self.enforce_flags,
+            flag_policy: self.flag_policy,
+            auto_draw: self.auto_draw,
+            en_passant_suffix: self.en_passant_suffix,
+            pgn_clock_comments: self.pgn_clock_comments,
+            clock: self.clock,
+            ..ChessGame::default()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    fn undo_game() -> ChessGame {
+        ChessGame {
+            allow_undo: true,
+            flag_policy: FlagPolicy::Generate,
+            ..ChessGame::default()
+        }
+    }
+
+    #[test]
+    fn board_ascii() {
+        let mut game = ChessGame {
+            rotate_board: RotateBoard::Black,
+            ..ChessGame::default()
+        };
+        let white = format!("{}", game.board());
+        let black = format!("{:#}", game.board());
+        assert_eq!(game.board_string(), black);
+        assert_eq!(game.board_ascii(RotateBoard::White), white);
+        assert_eq!(game.board_ascii(RotateBoard::Rotate), white);
+        assert!(!game.board_ascii(RotateBoard::White).contains('\x1b'));
+
+        game.play_moves(&["e4"]).unwrap();
+        assert_eq!(
+            game.board_ascii(RotateBoard::Rotate),
+            format!("{:#}", game.board())
+        );
+    }
+    #[test]
+    fn undo_restores_position() {
+        let mut game = undo_game();
+        game.play_moves(&["e4", "Nf6", "e5", "d5"]).unwrap();
+        let fen = game.gen_fen();
+        game.play_moves(&["exd6"]).unwrap();
+        assert_eq!(game.board().get(&board::Square::D5), None);
+        game.undo_move().unwrap();
+        assert_eq!(game.gen_fen(), fen);
+
+        game.play_moves(&["exd6", "Ng8", "dxc7", "Nf6"]).unwrap();
+        let fen = game.gen_fen();
+        game.play_moves(&["cxb8=Q"]).unwrap();
+        game.undo_move().unwrap();
+        assert_eq!(game.gen_fen(), fen);
+
+        let mut game = undo_game();
+        game.play_moves(&["e4", "e5", "Nf3", "Nc6", "Bc4", "Nf6"])
+            .unwrap();
+        let fen = game.gen_fen();
+        game.play_moves(&["O-O"]).unwrap();
+        game.undo_move().unwrap();
+        assert_eq!(game.gen_fen(), fen);
+
+        let mut game = ChessGame::default();
+        game.play_moves(&["e4"]).unwrap();
+        assert_eq!(game.undo_move(), None);
+    }
+    #[test]
+    fn redo_restores_move() {
+        let mut game = undo_game();
+        game.play_moves(&["e4", "e5", "Nf3"]).unwrap();
+        let expected = game.clone();
+
+        let mut game = undo_game();
+        game.play_moves(&["e4", "e5"]).unwrap();
+        assert_eq!(game.redo_move(), None);
+        game.play_moves(&["Nf3"]).unwrap();
+        game.undo_move().unwrap();
+        game.redo_move().unwrap();
+        assert_eq!(game.board(), expected.board());
+        assert_eq!(game.game_hist(), expected.game_hist());
+        assert_eq!(game.redo_move(), None);
+
+        game.undo_move().unwrap();
+        game.play_moves(&["Nc3"]).unwrap();
+        assert_eq!(game.redo_move(), None);
+    }
+    #[test]
+    fn undo_redo_introspection() {
+        let mut game = undo_game();
+        assert!(!game.can_undo());
+        assert!(!game.can_redo());
+        assert_eq!(game.ply_count(), 0);
+
+        game.play_moves(&["e4"]).unwrap();
+        assert!(game.can_undo());
+        assert!(!game.can_redo());
+        assert_eq!(game.ply_count(), 1);
+
+        game.undo_move().unwrap();
+        assert!(!game.can_undo());
+        assert!(game.can_redo());
+        assert_eq!(game.ply_count(), 0);
+
+        let mut game = ChessGame::default();
+        game.play_moves(&["e4"]).unwrap();
+        assert!(!game.can_undo());
+        assert_eq!(game.ply_count(), 1);
+    }
+    #[test]
+    fn last_move_squares() {
+        let mut game = undo_game();
+        assert_eq!(game.last_move(), None);
+        assert_eq!(game.last_move_squares(), None);
+
+        game.play_moves(&["e4"]).unwrap();
+        assert_eq!(game.last_move_squares(), Some((Square::E2, Square::E4)));
+
+        game.play_moves(&["e5", "Nf3", "Nc6", "Bc4", "Nf6", "O-O"])
+            .unwrap();
+        assert!(matches!(game.last_move(), Some(Turn::Castling(_, _))));
+        assert_eq!(game.last_move_squares(), Some((Square::E1, Square::G1)));
+        game.play_moves(&["Be7", "d3", "O-O"]).unwrap();
+        assert_eq!(game.last_move_squares(), Some((Square::E8, Square::G8)));
+    }
+    #[test]
+    fn set_position() {
+        let mut game = ChessGame {
+            rotate_board: RotateBoard::Black,
+            ..undo_game()
+        };
+        game.play_moves(&["e4", "e5"]).unwrap();
+        assert!(game.set_position("not a fen").is_err());
+        assert_eq!(game.ply_count(), 2);
+
+        game.set_position("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1")
+            .unwrap();
+        assert_eq!(game.ply_count(), 0);
+        assert!(!game.can_undo());
+        assert_eq!(game.rotate_board, RotateBoard::Black);
+        assert_eq!(game.game_state, GameState::Continue);
+        game.play_moves(&["Ra8"]).unwrap();
+        assert!(matches!(
+            game.game_state,
+            GameState::Win(Win {
+                is_white: true,
+                kind: WinType::Checkmate
+            })
+        ));
+        assert_eq!(game.fen_history()[0], "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        assert!(game
+            .gen_pgn()
+            .contains("[SetUp \"1\"]\n[FEN \"6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\"]\n"));
+
+        game.set_position("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1")
+            .unwrap();
+        assert!(matches!(game.game_state, GameState::Win(_)));
+
+        game.set_position("6k1/5ppp/8/8/8/8/8/R5K1 b - - 0 7")
+            .unwrap();
+        game.play_moves(&["h6", "Ra8"]).unwrap();
+        assert!(game.gen_pgn().ends_with("\n7... h6 8. Ra8+ *"));
+    }
+    #[test]
+    fn make_move_san() {
+        let mut game = ChessGame::default();
+        game.make_move_san("e4").unwrap();
+        assert_eq!(game.last_move_squares(), Some((Square::E2, Square::E4)));
+
+        let err = game.make_move_san("e9").unwrap_err();
+        assert!(matches!(err, Error::Parse(_)));
+        // the parse error and its kind, without the wrapper adding a line of its own
+        assert_eq!(utils::all_errors_string(&err).lines().count(), 2);
+        assert!(matches!(
+            game.make_move_san("Ke7"),
+            Err(Error::Turn(TurnError::NoTarget))
+        ));
+        assert_eq!(
+            game.make_move_san("Ke7").unwrap_err().to_string(),
+            TurnError::NoTarget.to_string()
+        );
+        assert_eq!(game.game_hist.len(), 1);
+    }
+    #[test]
+    fn error_display() {
+        let err = Error::from(TurnError::KingInCheck);
+        assert!(err
+            .to_string()
+            .contains("That move causes the king to be in check"));
+        assert!(std::error::Error::source(&err).is_none());
+
+        let err = Error::from("e9".parse::<Turn>().unwrap_err());
+        assert!(matches!(err, Error::Parse(_)));
+        assert!(std::error::Error::source(&err).is_some());
+    }
+    #[test]
+    fn lenient_flags() {
+        let mut game = ChessGame {
+            flag_policy: FlagPolicy::Lenient,
+            allow_undo: true,
             ..ChessGame::default()
+        };
+        assert!(matches!(
+            game.make_move(&"Nf3+".parse().unwrap()),
+            Err(TurnError::RemoveCheckSpecifier)
+        ));
+        assert!(matches!(
+            game.make_move(&"xe4".parse().unwrap()),
+            Err(TurnError::RemoveCaptureSpecifier)
+        ));
+        game.play_moves(&["e4", "Nf6", "a3", "Ng4", "b3", "Ne5", "d3"])
+            .unwrap();
+        game.play_moves(&["Nf3"]).unwrap();
+        assert!(game.last_move().unwrap().is_check());
+        game.undo_move().unwrap();
+        game.play_moves(&["Nf3+"]).unwrap();
+        assert!(game.last_move().unwrap().is_check());
+
+        let mut game = ChessGame::default();
+        assert!(matches!(
+            game.make_move(&"e4+".parse().unwrap()),
+            Err(TurnError::RemoveCheckSpecifier)
+        ));
+        assert_eq!(FlagPolicy::from(true), FlagPolicy::Enforce);
+        assert_eq!(FlagPolicy::from(false), FlagPolicy::Generate);
+        #[allow(deprecated)]
+        {
+            assert!(game.enforce_flags());
+            game.flag_policy = FlagPolicy::Lenient;
+            assert!(!game.enforce_flags());
         }
+
+        // moving a knight onto the en passant square isn't a capture
+        game.play_moves(&["Nc3", "e6", "Nb5", "d5"]).unwrap();
+        assert!(matches!(
+            game.make_move(&"Nxd6+".parse().unwrap()),
+            Err(TurnError::RemoveCaptureSpecifier)
+        ));
+        game.play_moves(&["Nd6"]).unwrap();
+        assert!(game.last_move().unwrap().is_check());
+        assert_eq!(game.last_move().unwrap().to_string(), "Nb5d6+");
+    }
+    #[test]
+    fn null_moves() {
+        let mut game = ChessGame::default();
+        assert!(matches!(
+            game.make_move(&Turn::Null),
+            Err(TurnError::NullMoveNotAllowed)
+        ));
+        assert!(game.make_move_san("--").is_err());
+        assert!(game.game_hist.is_empty());
+
+        let mut builder = ChessGame::builder();
+        builder.allow_null_moves(true);
+        let mut game = builder.build();
+        game.play_moves(&["e4", "--", "d4"]).unwrap();
+        assert_eq!(game.side_to_move(), Color::Black);
+        assert_eq!(game.game_hist.len(), 3);
+    }
+    #[test]
+    fn play_moves() {
+        let mut game = ChessGame::default();
+        game.play_moves(&["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6", "Qxf7#"])
+            .unwrap();
+        assert_eq!(
+            game.game_state,
+            GameState::Win(Win {
+                is_white: true,
+                kind: WinType::Checkmate,
+            })
+        );
+
+        let mut game = ChessGame::default();
+        assert!(matches!(
+            game.play_moves(&["e4", "e5", "Ke3"]),
+            Err((2, TurnError::NoTarget))
+        ));
+        assert_eq!(game.game_hist().len(), 2);
+        let err = game.play_moves(&["Nf3", "?"]).unwrap_err();
+        assert!(matches!(err, (1, TurnError::InvalidNotation(_))));
+        assert!(std::error::Error::source(&err.1).is_some());
+
+        let mut replayed = ChessGame::default();
+        replayed.play_turns(game.game_hist()).unwrap();
+        assert_eq!(replayed.board(), game.board());
+    }
+    #[test]
+    fn pgn_seven_tag_roster() {
+        let mut builder = ChessGame::builder();
+        builder
+            .flag_policy(FlagPolicy::Generate)
+            .players((String::from("Alice"), String::from("Bob \"B\"")));
+        let mut game = builder.build();
+        game.set_pgn_tag("Event", "Casual Game");
+        game.play_moves(&["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6", "Qxf7"])
+            .unwrap();
+        assert_eq!(
+            game.gen_pgn(),
+            "[Event \"Casual Game\"]\n\
+             [Site \"?\"]\n\
+             [Date \"????.??.??\"]\n\
+             [Round \"?\"]\n\
+             [White \"Alice\"]\n\
+             [Black \"Bob \\\"B\\\"\"]\n\
+             [Result \"1-0\"]\n\
+             \n\
+             1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0"
+        );
+    }
+    #[test]
+    fn pgn_claimable_draw_comment() {
+        let mut game = undo_game();
+        game.play_moves(&["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1"])
+            .unwrap();
+        assert_eq!(game.result_token(), "*");
+        assert!(!game.gen_pgn().contains('{'));
+
+        game.play_moves(&["Ng8"]).unwrap();
+        assert_eq!(game.result_token(), "*");
+        let pgn = game.gen_pgn().replace('\n', " ");
+        assert!(
+            pgn.ends_with("3. Nf3 Nf6 4. Ng1 Ng8 {Draw claimable by threefold repetition} *"),
+            "{pgn}"
+        );
+
+        game.claim_draw().unwrap();
+        assert_eq!(game.result_token(), "1/2-1/2");
+        assert!(!game.gen_pgn().contains('{'));
+    }
+    #[test]
+    fn pgn_en_passant_suffix() {
+        let mut game = ChessGame {
+            en_passant_suffix: true,
+            ..undo_game()
+        };
+        game.play_moves(&["e4", "Nf6", "e5", "d5", "exd6"]).unwrap();
+        let pgn = game.gen_pgn();
+        assert!(pgn.contains("3. exd6 e.p. *"), "{pgn}");
+        let (_, moves) = pgn::read_pgn(&pgn).unwrap();
+        assert_eq!(moves.last(), Some(&"exd6".parse::<Turn>().unwrap()));
+
+        // the check flag stays on the move
+        game.set_position("8/8/8/8/4Pp2/8/3K4/7k b - e3 0 1")
+            .unwrap();
+        game.play_moves(&["fxe3+"]).unwrap();
+        let pgn = game.gen_pgn();
+        assert!(pgn.contains("1... fxe3+ e.p. *"), "{pgn}");
+        let (_, moves) = pgn::read_pgn(&pgn).unwrap();
+        assert_eq!(moves, ["fxe3+".parse::<Turn>().unwrap()]);
+    }
+    #[test]
+    fn pgn_line_wrapping() {
+        let pgn_string = std::fs::read_to_string("res/test.pgn").unwrap();
+        let (_, moves) = pgn::read_pgn(&pgn_string).unwrap();
+        let mut game = undo_game();
+        for r#move in &moves {
+            game.make_move(r#move).unwrap();
+        }
+        let pgn = game.gen_pgn();
+        let movetext = pgn.split("\n\n").nth(1).unwrap();
+        assert!(movetext.lines().count() > 1);
+        for line in movetext.lines() {
+            assert!(line.len() <= PGN_LINE_WIDTH, "{line}");
+            assert!(!line.starts_with(' ') && !line.ends_with(' '));
+            // a move number is never separated from white's move
+            assert!(!line.ends_with('.'));
+        }
+
+        let (_, reread) = pgn::read_pgn(&pgn).unwrap();
+        assert_eq!(reread.len(), moves.len());
+        let mut replayed = undo_game();
+        for r#move in &reread {
+            replayed.make_move(r#move).unwrap();
+        }
+        assert_eq!(replayed.gen_fen(), game.gen_fen());
+    }
+    #[test]
+    fn pgn_game_info_tags() {
+        let mut game = ChessGame::default();
+        assert_eq!(game.player_name(true), "White");
+        game.game_info
+            .insert(String::from("Black"), String::from("Carol"));
+        game.game_info
+            .insert(String::from("TimeControl"), String::from("300+2"));
+        game.set_pgn_tag("Annotator", "Dave");
+        game.set_pgn_tag("Result", "0-1");
+        assert_eq!(game.player_name(false), "Carol");
+        assert_eq!(game.player_string(), "White's turn");
+
+        let pgn = game.gen_pgn();
+        assert!(pgn.contains("[Black \"Carol\"]\n"));
+        assert!(pgn.contains("[Result \"*\"]\n[Annotator \"Dave\"]\n[TimeControl \"300+2\"]\n\n"));
+        assert!(!pgn.contains("0-1"));
+    }
+    #[test]
+    fn claim_threefold_repetition() {
+        let knight_shuffle = ["Nf3", "Nf6", "Ng1", "Ng8"];
+        let mut game = undo_game();
+        game.play_moves(&knight_shuffle).unwrap();
+        assert_eq!(game.can_claim_draw(), None);
+        assert_eq!(game.claim_draw(), Err(DrawOfferError::NoClaimableDraw));
+        game.play_moves(&knight_shuffle).unwrap();
+        assert_eq!(game.game_state, GameState::Continue);
+        assert_eq!(game.can_claim_draw(), Some(DrawType::ThreefoldRepetition));
+        game.undo_move().unwrap();
+        assert_eq!(game.can_claim_draw(), None);
+        assert_eq!(game.position_counter.total(), game.undo_stack.len());
+        game.play_moves(&["Ng8"]).unwrap();
+        assert_eq!(game.can_claim_draw(), Some(DrawType::ThreefoldRepetition));
+        assert!(game.claim_draw().is_ok());
+        assert_eq!(
+            game.game_state,
+            GameState::Draw(DrawType::ThreefoldRepetition)
+        );
+
+        // fivefold repetition ends the game without a claim
+        let mut game = undo_game();
+        for _ in 0..4 {
+            game.play_moves(&knight_shuffle).unwrap();
+        }
+        assert_eq!(
+            game.game_state,
+            GameState::Draw(DrawType::FivefoldRepetition)
+        );
+
+        let mut game = ChessGame {
+            auto_draw: true,
+            ..undo_game()
+        };
+        game.play_moves(&knight_shuffle).unwrap();
+        game.play_moves(&knight_shuffle).unwrap();
+        assert_eq!(
+            game.game_state,
+            GameState::Draw(DrawType::ThreefoldRepetition)
+        );
+    }
+    #[test]
+    fn board_at_ply() {
+        let mut game = ChessGame::default();
+        assert_eq!(game.board_at_ply(0), Some(ChessBoard::default()));
+        assert_eq!(game.board_at_ply(1), None);
+
+        game.play_moves(&["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "O-O"])
+            .unwrap();
+        assert_eq!(game.board_at_ply(0), Some(ChessBoard::default()));
+        assert_eq!(
+            game.board_at_ply(2).unwrap().to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+        );
+        assert_eq!(game.board_at_ply(7).as_ref(), Some(game.board()));
+        assert_eq!(game.board_at_ply(8), None);
+    }
+    #[test]
+    fn replay() {
+        let mut game = ChessGame::default();
+        assert_eq!(game.replay().count(), 0);
+
+        game.play_moves(&["d4", "d5", "c4", "dxc4", "e4", "b5", "a4", "c6"])
+            .unwrap();
+        let mut plies = 0;
+        for (ply, turn, board) in game.replay() {
+            plies += 1;
+            assert_eq!(ply, plies);
+            assert_eq!(turn, game.game_hist()[ply - 1]);
+            assert_eq!(game.board_at_ply(ply), Some(board));
+        }
+        assert_eq!(plies, 8);
+        assert_eq!(game.replay().last().unwrap().2, *game.board());
+    }
+    #[test]
+    fn fen_history() {
+        let mut game = ChessGame::default();
+        assert_eq!(game.fen_history(), vec![game.gen_fen()]);
+
+        game.play_moves(&["Nf3", "d5", "Ng1", "d4", "e4", "dxe3"])
+            .unwrap();
+        let fens = game.fen_history();
+        assert_eq!(
+            fens,
+            [
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                "rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R b KQkq - 1 1",
+                "rnbqkbnr/ppp1pppp/8/3p4/8/5N2/PPPPPPPP/RNBQKB1R w KQkq d6 0 2",
+                "rnbqkbnr/ppp1pppp/8/3p4/8/8/PPPPPPPP/RNBQKBNR b KQkq - 1 2",
+                "rnbqkbnr/ppp1pppp/8/8/3p4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 3",
+                "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3",
+                "rnbqkbnr/ppp1pppp/8/8/8/4p3/PPPP1PPP/RNBQKBNR w KQkq - 0 4",
+            ]
+        );
+        assert_eq!(fens.last(), Some(&game.gen_fen()));
+    }
+    #[test]
+    fn resign() {
+        let mut game = ChessGame::default();
+        game.resign();
+        assert_eq!(
+            game.game_state,
+            GameState::Win(Win {
+                is_white: false,
+                kind: WinType::Resign,
+            })
+        );
+
+        let mut game = ChessGame::default();
+        game.play_moves(&["e4"]).unwrap();
+        game.resign();
+        assert_eq!(
+            game.game_state,
+            GameState::Win(Win {
+                is_white: true,
+                kind: WinType::Resign,
+            })
+        );
+    }
+    #[test]
+    fn draw_offer() {
+        let mut game = ChessGame::default();
+        assert_eq!(game.accept_draw(), Err(DrawOfferError::NoOffer));
+        assert_eq!(game.game_state, GameState::Continue);
+
+        // offer -> accept -> draw
+        game.play_moves(&["e4"]).unwrap();
+        game.offer_draw();
+        assert_eq!(game.draw_offered_by(), Some(Color::Black));
+        assert_eq!(game.accept_draw(), Err(DrawOfferError::OwnOffer));
+        assert_eq!(game.draw_offered_by(), Some(Color::Black));
+        game.play_moves(&["e5"]).unwrap();
+        assert!(game.accept_draw().is_ok());
+        assert_eq!(game.game_state, GameState::Draw(DrawType::Agreement));
+        assert_eq!(game.draw_offered_by(), None);
+
+        // offer -> decline -> continue
+        let mut game = ChessGame::default();
+        assert!(game.decline_draw().is_err());
+        game.offer_draw();
+        game.play_moves(&["e4"]).unwrap();
+        assert_eq!(game.draw_offered_by(), Some(Color::White));
+        assert!(game.decline_draw().is_ok());
+        assert_eq!(game.draw_offered_by(), None);
+        assert!(game.accept_draw().is_err());
+        game.play_moves(&["e5"]).unwrap();
+        assert_eq!(game.game_state, GameState::Continue);
+    }
+    #[test]
+    fn draw_offer_expires() {
+        let mut game = ChessGame::default();
+        game.offer_draw();
+        game.play_moves(&["e4", "e5"]).unwrap();
+        assert_eq!(game.draw_offered_by(), Some(Color::White));
+        game.play_moves(&["Nf3"]).unwrap();
+        assert_eq!(game.draw_offered_by(), None);
+        assert!(game.accept_draw().is_err());
+    }
+    #[test]
+    fn outcome() {
+        let mut game = undo_game();
+        assert_eq!(game.outcome(), None);
+        game.play_moves(&["f3", "e5", "g4", "Qh4"]).unwrap();
+        assert_eq!(game.outcome(), Some(Outcome::BlackWins(WinType::Checkmate)));
+        assert_eq!(game.outcome().unwrap().pgn_result_token(), "0-1");
+        assert!(game.gen_pgn().contains("[Result \"0-1\"]"));
+
+        let outcomes = [
+            (GameState::Stop, Outcome::Aborted, "*"),
+            (
+                GameState::Draw(DrawType::Stalemate),
+                Outcome::Draw(DrawType::Stalemate),
+                "1/2-1/2",
+            ),
+            (
+                GameState::Win(Win {
+                    is_white: true,
+                    kind: WinType::Resign,
+                }),
+                Outcome::WhiteWins(WinType::Resign),
+                "1-0",
+            ),
+        ];
+        for (game_state, outcome, token) in outcomes {
+            game.game_state = game_state;
+            assert_eq!(game.outcome(), Some(outcome));
+            assert_eq!(outcome.pgn_result_token(), token);
+        }
+    }
+    #[test]
+    fn clock_flag_fall() {
+        let timed_game = |initial| {
+            let mut builder = ChessGame::builder();
+            builder
+                .flag_policy(FlagPolicy::Generate)
+                .clock(initial, Duration::from_secs(1));
+            builder.build()
+        };
+        let timeout = GameState::Win(Win {
+            is_white: false,
+            kind: WinType::Timeout,
+        });
+        assert_eq!(ChessGame::default().time_remaining(true), None);
+
+        let mut game = timed_game(Duration::from_secs(3600));
+        game.play_moves(&["e4"]).unwrap();
+        assert!(game.time_remaining(true).unwrap() > Duration::from_secs(3600));
+        assert!(!game.clock.unwrap().is_flagged(false));
+        game.flag();
+        assert_eq!(game.game_state, GameState::Continue);
+
+        // with no time on the clock, white has run out before making a move
+        let mut game = timed_game(Duration::ZERO);
+        assert!(game.clock.unwrap().is_flagged(true));
+        game.flag();
+        assert_eq!(game.game_state, timeout);
+
+        // a move made after running out loses on time, without adding the increment
+        let mut game = timed_game(Duration::ZERO);
+        game.play_moves(&["e4"]).unwrap();
+        assert_eq!(game.time_remaining(true), Some(Duration::ZERO));
+        assert_eq!(game.game_state, timeout);
+    }
+    #[test]
+    fn pgn_clock_comments() {
+        let mut builder = ChessGame::builder();
+        builder
+            .flag_policy(FlagPolicy::Generate)
+            .allow_undo(true)
+            .pgn_clock_comments(true)
+            .clock(Duration::from_secs(180), Duration::from_secs(2));
+        let mut game = builder.build();
+        game.play_moves(&["e4", "e5"]).unwrap();
+        let pgn = game.gen_pgn();
+        assert!(
+            pgn.contains("1. e4 {[%clk 0:03:01]} e5 {[%clk 0:03:01]} *"),
+            "{pgn}"
+        );
+        let (_, moves) = pgn::read_pgn(&pgn).unwrap();
+        assert_eq!(moves.len(), 2);
+
+        game.undo_move().unwrap();
+        assert!(game.gen_pgn().contains("1. e4 {[%clk 0:03:01]} *"));
+        game.redo_move().unwrap();
+        assert_eq!(game.gen_pgn().matches("[%clk").count(), 2);
+
+        game.pgn_clock_comments = false;
+        assert!(!game.gen_pgn().contains("[%clk"));
+        let mut untimed = ChessGame {
+            pgn_clock_comments: true,
+            ..undo_game()
+        };
+        untimed.play_moves(&["e4"]).unwrap();
+        assert!(!untimed.gen_pgn().contains("[%clk"));
+        assert_eq!(pgn_clock(Duration::from_secs(3725)), "1:02:05");
     }
 }
@@ -2,11 +2,11 @@
 mod tui;
 
 use chess::{
-    board::{DrawType, GameState, Square, Win, WinType},
-    pieces::Piece,
+    board::{ChessBoard, GameState, Square},
+    pieces::{Piece, PieceType},
     turn::Turn,
     utils::all_errors_string,
-    ChessGame,
+    ChessGame, FlagPolicy,
 };
 use crossterm::event::{self, Event, KeyCode, KeyEventKind, MouseButton};
 use ratatui::{
@@ -51,6 +51,7 @@ struct App {
     character_index: usize,
     board_location: Square,
     selected_piece: Option<(Square, Piece)>,
+    pending_promotion: Option<Turn>,
     input_mode: InputMode,
     messages: Vec<String>,
     last_input_was_keyboard: bool,
@@ -67,6 +68,7 @@ impl App {
             character_index: 0,
             board_location: Square::A1,
             selected_piece: None,
+            pending_promotion: None,
             messages: Vec::new(),
             last_input_was_keyboard: true,
             saved_location: Square::A1,
@@ -131,45 +133,11 @@ impl App {
         self.reset_cursor();
     }
     fn handle_gamestate(&mut self) {
-        match self.game.game_state {
-            GameState::Continue => return,
-            GameState::Win(win) => {
-                let win_message = String::from(if !win.is_white {
-                    self.game
-                        .game_info
-                        .get("White")
-                        .map_or("White", |x| x.as_ref())
-                } else {
-                    self.game
-                        .game_info
-                        .get("Black")
-                        .map_or("Black", |x| x.as_ref())
-                }) + " wins by "
-                    + match win.kind {
-                        WinType::Checkmate => "checkmate",
-                        WinType::Resign => "resignation",
-                        WinType::Timeout => "timout",
-                    };
-                self.messages.clear();
-                self.messages.push(win_message);
-            }
-            GameState::Draw(draw) => {
-                let draw_message = String::from("The game is a draw by ")
-                    + match draw {
-                        DrawType::Stalemate => "stalemate",
-                        DrawType::FiftyMove => "the fifty move rule",
-                        DrawType::ThreefoldRepitition => "threefold repetition",
-                        DrawType::InsufficientMaterial => "insufficient material",
-                        DrawType::Offer => "draw offer",
-                    };
-                self.messages.clear();
-                self.messages.push(draw_message);
-            }
-            GameState::Stop => {
-                self.messages.clear();
-                self.messages.push(String::from("The game was aborted"));
-            }
-        }
+        let Some(message) = self.game.end_message() else {
+            return;
+        };
+        self.messages.clear();
+        self.messages.push(message);
         self.messages.push(String::from("Press any key to quit"));
         self.stop = true;
     }
@@ -188,10 +156,33 @@ impl App {
             return;
         };
         let turn = Turn::new(selected_piece, self.board_location);
-        let enforce_flags = self.game.enforce_flags;
-        self.game.enforce_flags = false;
+        if self.game.board().needs_promotion(&turn) {
+            self.pending_promotion = Some(turn);
+            self.messages
+                .push(String::from("Choose a piece to promote to: q, r, b, or n"));
+            return;
+        }
+        self.play_visual_turn(turn);
+    }
+    fn promote(&mut self, piece_char: char) {
+        self.messages.clear();
+        let Some(turn) = self.pending_promotion.take() else {
+            return;
+        };
+        match PieceType::try_from(piece_char.to_ascii_uppercase()) {
+            Ok(piece) if PieceType::PROMOTABLE.contains(&piece) => {
+                self.play_visual_turn(turn.with_promotion(piece))
+            }
+            _ => self
+                .messages
+                .push(format!("`{piece_char}` is not a piece to promote to")),
+        }
+    }
+    fn play_visual_turn(&mut self, turn: Turn) {
+        let flag_policy = self.game.flag_policy;
+        self.game.flag_policy = FlagPolicy::Generate;
         self.handle_turn(turn);
-        self.game.enforce_flags = enforce_flags;
+        self.game.flag_policy = flag_policy;
     }
     fn handle_turn(&mut self, turn: Turn) {
         match self.game.make_move(&turn) {
@@ -208,22 +199,58 @@ impl App {
     }
     fn handle_input(&mut self) {
         match self.input.trim() {
-            "undo" => match self.game.undo_move() {
-                Some(_) => return,
-                None => {
+            "undo" => {
+                if self.game.can_undo() {
+                    self.game.undo_move();
+                } else if !self.game.allow_undo {
                     self.messages
                         .extend_from_slice(&["Undoing is not allowed".to_string()]);
+                } else {
+                    self.messages
+                        .extend_from_slice(&["There is no move to undo".to_string()]);
+                }
+                return;
+            }
+            "redo" => {
+                if self.game.can_redo() {
+                    self.game.redo_move();
+                } else {
+                    self.messages
+                        .extend_from_slice(&["There is no move to redo".to_string()]);
+                }
+                return;
+            }
+            "claim" => match self.game.claim_draw() {
+                Ok(_) => (),
+                Err(err) => {
+                    self.messages.push(err.to_string());
                     return;
                 }
             },
             "quit" => self.game.game_state = GameState::Stop,
-            "resign" => {
-                self.game.game_state = GameState::Win(Win {
-                    is_white: self.game.is_white(),
-                    kind: WinType::Resign,
-                })
+            "resign" => self.game.resign(),
+            "draw" => {
+                if self.game.draw_offered_by() == Some(!self.game.side_to_move()) {
+                    let _ = self.game.accept_draw();
+                } else {
+                    self.game.offer_draw();
+                    let message = format!(
+                        "{} offers a draw, after this move {} can enter `draw` to accept or \
+                         `decline` to decline",
+                        self.game.player_name(self.game.side_to_move()),
+                        self.game.player_name(!self.game.side_to_move())
+                    );
+                    self.messages.push(message);
+                    return;
+                }
+            }
+            "decline" => {
+                if self.game.decline_draw().is_err() {
+                    self.messages
+                        .extend_from_slice(&["There is no draw offer to decline".to_string()]);
+                }
+                return;
             }
-            "draw" => self.game.game_state = GameState::Draw(DrawType::Offer),
             _ => (),
         }
         if self.game.game_state != GameState::Continue {
@@ -263,9 +290,7 @@ impl App {
     fn select_or_move(&mut self) {
         let potential_piece = self.game.board().get(&self.board_location);
         match potential_piece {
-            Some(Piece { is_white, .. }) if *is_white == self.game.is_white() => {
-                self.select_piece()
-            }
+            Some(Piece { color, .. }) if *color == self.game.side_to_move() => self.select_piece(),
             Some(_) | None => self.move_piece(),
         }
     }
@@ -289,6 +314,9 @@ fn run_app(terminal: &mut Tui, mut app: App) -> io::Result<()> {
                 app.last_input_was_keyboard = true;
                 match app.input_mode {
                     InputMode::Visual => match key.code {
+                        KeyCode::Char(piece) if app.pending_promotion.is_some() => {
+                            app.promote(piece)
+                        }
                         KeyCode::Char('a') => {
                             app.selected_piece = None;
                             app.input_mode = InputMode::Algebraic;
@@ -383,17 +411,17 @@ fn render_history(app: &App, f: &mut Frame, move_area: ratatui::prelude::Rect) {
         .game
         .game_hist()
         .chunks(2)
-        .scan(ChessGame::default(), |board, turns| {
+        .scan(ChessBoard::default(), |board, turns| {
             let mut turn_string = String::new();
 
             let turn1 = board.get_minimum_move(&turns[0]);
-            board.make_move(&turn1).expect("History is always valid");
+            board.update_board(&turns[0]);
 
             turn_string += &turn1.to_string();
 
             if let Some(turn2) = turns.get(1) {
                 let turn2 = board.get_minimum_move(turn2);
-                board.make_move(&turn2).expect("History is always valid");
+                board.update_board(&turns[1]);
 
                 turn_string.push(' ');
                 turn_string += &turn2.to_string();
@@ -444,7 +472,7 @@ fn render_info(app: &App, f: &mut Frame, info_area: ratatui::prelude::Rect) {
         ]),
         InputMode::Algebraic => Paragraph::new(vec![
             "Press `esc` to enter visual mode".into(),
-            "`quit` `resign` `draw` to end the game".into(),
+            "`quit` `resign` `draw` `claim` to end the game".into(),
             "Enter a move in algebraic chess notation to make a move with commands".into(),
         ]),
     }
@@ -479,6 +507,12 @@ fn render_input(app: &App, f: &mut Frame, input_area: ratatui::prelude::Rect) {
 fn render_board(app: &App, f: &mut Frame, board_area: ratatui::prelude::Rect) {
     let player_string = app.game.player_string();
 
+    let last_move = app.game.last_move_squares();
+    let destinations = app
+        .selected_piece
+        .map(|(selected_square, _)| app.game.board().legal_destinations(selected_square))
+        .unwrap_or_default();
+
     let mut all_square_strs = Square::iterator()
         .map(|sq| (sq, app.game.board().get(&sq)))
         .map(|(sq, pc)| {
@@ -487,16 +521,15 @@ fn render_board(app: &App, f: &mut Frame, board_area: ratatui::prelude::Rect) {
             } else {
                 "  ".to_string()
             };
-            if let Some((selected_square, _)) = app.selected_piece {
-                if selected_square == sq {
-                    pc_string.bg(Color::LightYellow)
-                } else {
-                    pc_string.bg(if sq.is_light() {
-                        Color::White
-                    } else {
-                        Color::LightGreen
-                    })
-                }
+            if app
+                .selected_piece
+                .is_some_and(|(selected_square, _)| selected_square == sq)
+            {
+                pc_string.bg(Color::LightYellow)
+            } else if destinations.contains(&sq) {
+                pc_string.bg(Color::Yellow)
+            } else if last_move.is_some_and(|(src, dst)| src == sq || dst == sq) {
+                pc_string.bg(Color::LightBlue)
             } else {
                 pc_string.bg(if sq.is_light() {
                     Color::White
@@ -577,13 +610,25 @@ fn square_to_location(sq: Square) -> (u16, u16) {
 
 #[allow(dead_code)]
 mod basic {
-    use chess::utils::print_all_errors;
-    use chess::{board::*, turn::*, *};
-    use itertools::Itertools;
-    use pgn::read_pgn;
-    use std::io::BufRead;
+    use chess::utils::all_errors_string;
+    use chess::{board::*, *};
     use std::{fs, io};
 
+    /// Clears the terminal and prints the board, depending on the perspective set in
+    /// rotate_board, along with whose turn it is
+    fn display(game: &ChessGame) {
+        const ED0: &str = "\x1b[J";
+        const CUP: &str = "\x1b[H";
+        print!("{CUP}{ED0}{}", game.board_string());
+        println!("{}", game.player_string());
+    }
+    /// Prints the ending message describing the type of win, prints nothing if the game is ongoing
+    fn display_end_message(game: &ChessGame) {
+        if let Some(message) = game.end_message() {
+            println!("{message}");
+        }
+    }
+
     pub fn main_play_game() {
         // let mut buf = String::new();
         // println!("Whose perspective? (W, B, R)");
@@ -603,20 +648,15 @@ mod basic {
         //    .enforce_flags(true)
         //    .build();
         //let _pgn_string = fs::read_to_string("res/pgn.pgn").expect("Valid file");
-        let pgn_iter = io::BufReader::new(
+        let pgn_reader = pgn::PgnReader::new(io::BufReader::new(
             fs::File::open("/mnt/c/Users/jungo/Downloads/sicilian.pgn").expect("Valid file"),
-        )
-        .lines()
-        .map_while(Result::ok)
-        .chunk_by(|line| line.starts_with('['));
-        let pgn_iter = pgn_iter
-            .into_iter()
-            .map(|(_, mut lines)| lines.join("\n"))
-            .chunks(2);
-        let pgn_iter = pgn_iter.into_iter().map(|mut lines| lines.join("\n"));
-
-        for pgn_string in pgn_iter {
-            play_from_pgn(&mut ChessGame::default(), pgn_string);
+        ));
+
+        for pgn in pgn_reader {
+            match pgn {
+                Ok(pgn) => play_pgn(&mut ChessGame::default(), pgn),
+                Err(e) => println!("Could not read the pgn file: {}", all_errors_string(&e)),
+            }
         }
 
         //play_from_pgn(&mut game, _pgn_string);
@@ -627,15 +667,31 @@ mod basic {
     }
     /// plays a full game of chess on a local machine, swapping between players
     pub fn play_game(game: &mut ChessGame) {
-        game.display();
+        display(game);
         if game.game_state != GameState::Continue {
-            game.display_end_message();
+            display_end_message(game);
             return;
         }
 
         let mut buf = String::new();
         loop {
             let outcome: Result<GameState, Box<dyn std::error::Error>> = (|| {
+                if game.draw_offered_by() == Some(!game.side_to_move()) {
+                    println!("Accept draw offer? (y/n)");
+                    loop {
+                        buf.clear();
+                        std::io::stdin().read_line(&mut buf)?;
+                        if buf.to_lowercase().trim() == "y" {
+                            game.accept_draw()?;
+                            return Ok(game.game_state);
+                        } else if buf.to_lowercase().trim() == "n" {
+                            game.decline_draw()?;
+                            break;
+                        } else {
+                            println!("Enter `y` or `n`");
+                        }
+                    }
+                }
                 buf.clear();
                 std::io::stdin().read_line(&mut buf)?;
 
@@ -649,31 +705,20 @@ mod basic {
                     }
                     "q" => return Ok(GameState::Stop),
                     "resign" => {
-                        return Ok(GameState::Win(Win {
-                            is_white: !game.is_white(),
-                            kind: WinType::Resign,
-                        }))
+                        game.resign();
+                        return Ok(game.game_state);
                     }
                     "draw" => {
-                        println!("Accept draw offer? (y/n)");
-                        loop {
-                            buf.clear();
-                            std::io::stdin().read_line(&mut buf)?;
-                            if buf.to_lowercase().trim() == "y" {
-                                return Ok(GameState::Draw(DrawType::Offer));
-                            } else if buf.to_lowercase().trim() == "n" {
-                                return Ok(GameState::Continue);
-                            } else {
-                                println!("Enter `y` or `n`");
-                                continue;
-                            }
-                        }
+                        // the opponent answers the offer once this move is made
+                        game.offer_draw();
+                        println!("Draw offered, enter your move");
+                        buf.clear();
+                        std::io::stdin().read_line(&mut buf)?;
                     }
                     _ => (),
                 }
 
-                let turn = buf.trim().parse::<Turn>()?;
-                game.make_move(&turn)?;
+                game.make_move_san(buf.trim())?;
                 Ok(GameState::Continue)
             })();
             match outcome {
@@ -686,31 +731,44 @@ mod basic {
                     GameState::Continue => (),
                 },
                 Err(e) => {
-                    print_all_errors(e.as_ref());
+                    println!("{}", all_errors_string(e.as_ref()));
                     continue;
                 }
             }
 
-            game.display();
+            display(game);
 
             if game.game_state == GameState::Continue {
                 continue;
             } else {
-                game.display_end_message();
+                display_end_message(game);
                 return;
             }
         }
     }
     /// plays a game of chess from a pgn string, progressing when <Enter> is pressed
     pub fn play_from_pgn(game: &mut ChessGame, pgn_string: String) {
-        let (game_info, moves) = pgn::read_pgn(&pgn_string);
-        let game_result = match game_info.get("Result") {
-            Some(result) => result.to_string(),
-            None => match pgn::get_game_result(&pgn_string) {
-                Some(result) => result.to_string(),
-                None => String::new(),
-            },
+        let pgn = pgn::read_pgn(&pgn_string).map(|(mut game_info, moves)| {
+            if let Some(result) = pgn::get_game_result(&pgn_string) {
+                game_info
+                    .entry(String::from("Result"))
+                    .or_insert_with(|| result.to_string());
+            }
+            (game_info, moves)
+        });
+        play_pgn(game, pgn);
+    }
+    /// plays a game of chess read from a pgn, progressing when <Enter> is pressed
+    pub fn play_pgn(game: &mut ChessGame, pgn: pgn::PgnResult) {
+        let (game_info, moves) = match pgn {
+            Ok(pgn) => pgn,
+            Err((index, e)) => {
+                println!("Could not read move {} of the pgn", index + 1);
+                println!("{}", all_errors_string(&e));
+                return;
+            }
         };
+        let game_result = game_info.get("Result").cloned().unwrap_or_default();
         if let Some(white) = game_info.get("White") {
             game.game_info.insert(String::from("White"), white.clone());
         }
@@ -718,17 +776,17 @@ mod basic {
             game.game_info.insert(String::from("Black"), black.clone());
         }
 
-        game.display();
+        display(game);
 
         let move_read_result = (|| -> Result<GameState, Box<dyn std::error::Error>> {
             for r#move in moves {
                 //std::io::stdin().read_line(&mut String::new())?;
                 game.make_move(&r#move)?;
-                game.display();
+                display(game);
             }
 
             match game_result.as_str() {
-                "1/2-1/2" => Ok(GameState::Draw(DrawType::Offer)),
+                "1/2-1/2" => Ok(GameState::Draw(DrawType::Agreement)),
                 "1-0" => Ok(GameState::Win(Win {
                     is_white: true,
                     kind: WinType::Resign,
@@ -748,13 +806,13 @@ mod basic {
                     GameState::Continue => play_game(game),
                     _ => {
                         game.game_state = game_result;
-                        game.display_end_message();
+                        display_end_message(game);
                     }
                 },
-                _ => game.display_end_message(),
+                _ => display_end_message(game),
             },
             Err(e) => {
-                print_all_errors(e.as_ref());
+                println!("{}", all_errors_string(e.as_ref()));
             }
         }
     }
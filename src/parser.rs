@@ -1,7 +1,9 @@
+use alloc::{string::String, vec::Vec};
+use core::fmt::Display;
+#[cfg(feature = "std")]
 use std::error::Error;
-use std::fmt::Display;
 
-use crate::board::{Line, Source, Square};
+use crate::board::{ChessBoard, Line, Source, Square};
 use crate::pieces::PieceType;
 use crate::turn::{flags, CastlingType, Move, Turn};
 
@@ -14,10 +16,11 @@ pub struct ChessParseError {
     pub kind: ParseErrorKind,
 }
 impl Display for ChessParseError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "Invalid input string due to: {}", self.character)
     }
 }
+#[cfg(feature = "std")]
 impl Error for ChessParseError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         Some(&self.kind)
@@ -41,8 +44,11 @@ pub enum ParseErrorKind {
     ConversionError(ConversionError),
     /// Failed to parse a FEN string
     InvalidFen,
+    /// No piece at the source square of a move
+    MissingPiece,
 }
 
+#[cfg(feature = "std")]
 impl Error for ParseErrorKind {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
@@ -53,11 +59,12 @@ impl Error for ParseErrorKind {
             Self::ConversionError(e) => Some(e),
             Self::PromotionError(e) => Some(e),
             Self::InvalidFen => None,
+            Self::MissingPiece => None,
         }
     }
 }
 impl Display for ParseErrorKind {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::InvalidChars => write!(f, "Invalid input character"),
             Self::ExcessPieces => write!(f, "Can only have one piece (uppercase letter) per move"),
@@ -66,6 +73,7 @@ impl Display for ParseErrorKind {
             Self::PromotionError(_) => write!(f, "Invalid promotion specified"),
             Self::ConversionError(_) => write!(f, "Couldn't convert the string into a valid move"),
             Self::InvalidFen => write!(f, "Couldn't convert the string into a valid board state"),
+            Self::MissingPiece => write!(f, "There is no piece on the source square"),
         }
     }
 }
@@ -80,9 +88,10 @@ pub enum PromotionError {
     /// A pawn was specified to promote to an invalid piece
     Invalid(PieceType),
 }
+#[cfg(feature = "std")]
 impl Error for PromotionError {}
 impl Display for PromotionError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Must => write!(f, "Must promote when reaching the final rank"),
             Self::Cant => write!(f, "Can't promote until reaching the final rank"),
@@ -99,19 +108,30 @@ pub struct ConversionError {
     /// Output target, represents the name of the type
     pub target: String,
 }
+#[cfg(feature = "std")]
 impl Error for ConversionError {}
 impl Display for ConversionError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "Cannot convert {0} into {1}", self.input, self.target)
     }
 }
 
 /// Parses a move from an algebraic chess notation string.
 ///
+/// Coordinate notation with a dash between the source and destination squares, such as `e2-e4`
+/// or `Ng1-f3`, is also accepted and parsed the same as a move with a source square
+///
+/// A trailing `e.p.` marker on an en passant capture, such as `exd6 e.p.`, is ignored, and a
+/// null move is written as `--` or `Z0`
+///
 /// # Errors
 ///
 /// Returns an error if the input string is not valid algebraic notation.
 pub fn parse_move(input: &str) -> Result<Turn, ChessParseError> {
+    let input = strip_en_passant(input);
+    if matches!(input, "--" | "Z0") {
+        return Ok(Turn::Null);
+    }
     if !input
         .chars()
         .all(|c| "abcdefgh12345678+#x=-O0KQRBN".contains(c))
@@ -128,6 +148,7 @@ pub fn parse_move(input: &str) -> Result<Turn, ChessParseError> {
     if let Some(castling) = parse_castling(input) {
         return Ok(Turn::Castling(castling, get_flags(input)));
     }
+    verify_dash(input)?;
 
     let mut piece = get_piece(input)?;
     let (dst, src) = get_squares(input)?;
@@ -152,6 +173,147 @@ pub fn parse_move(input: &str) -> Result<Turn, ChessParseError> {
     })
 }
 
+/// Removes a trailing `e.p.` marker, in any case and with or without a space before it
+fn strip_en_passant(input: &str) -> &str {
+    let trimmed = input.trim_end();
+    match trimmed.len().checked_sub(4) {
+        Some(start)
+            if trimmed.is_char_boundary(start) && trimmed[start..].eq_ignore_ascii_case("e.p.") =>
+        {
+            trimmed[..start].trim_end()
+        }
+        _ => input,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// The language of the piece letters in algebraic notation
+pub enum PieceLanguage {
+    #[default]
+    /// `K`, `Q`, `R`, `B`, and `N`
+    English,
+    /// `K`, `D`, `T`, `L`, and `S`
+    German,
+    /// `R`, `D`, `T`, `F`, and `C`
+    French,
+    /// `R`, `D`, `T`, `A`, and `C`
+    Spanish,
+}
+
+impl PieceLanguage {
+    /// Returns the letters of the king, queen, rook, bishop, and knight, in that order
+    pub fn letters(&self) -> [char; 5] {
+        match self {
+            PieceLanguage::English => ['K', 'Q', 'R', 'B', 'N'],
+            PieceLanguage::German => ['K', 'D', 'T', 'L', 'S'],
+            PieceLanguage::French => ['R', 'D', 'T', 'F', 'C'],
+            PieceLanguage::Spanish => ['R', 'D', 'T', 'A', 'C'],
+        }
+    }
+}
+
+/// Parses a move from an algebraic chess notation string that uses the piece letters of the
+/// given language, such as `Sf3` in German for `Nf3`
+///
+/// # Errors
+///
+/// Returns an error if a piece letter isn't used by the language, or if the input string is not
+/// valid algebraic notation.
+pub fn parse_move_localized(input: &str, lang: PieceLanguage) -> Result<Turn, ChessParseError> {
+    let english = PieceLanguage::English.letters();
+    let letters = lang.letters();
+    let translated = strip_en_passant(input)
+        .chars()
+        .map(|c| match letters.iter().position(|&letter| letter == c) {
+            Some(i) => Ok(english[i]),
+            // castling is written with the letter O in every language
+            None if c.is_uppercase() && c != 'O' => Err(ChessParseError {
+                character: c,
+                kind: ParseErrorKind::InvalidChars,
+            }),
+            None => Ok(c),
+        })
+        .collect::<Result<String, _>>()?;
+    parse_move(&translated)
+}
+
+/// Parses a move from a UCI long algebraic notation string, such as `e2e4` or `e7e8q`, or the
+/// null move `0000`.
+///
+/// The board is used to determine the type of the moving piece, and a king moving two files
+/// is read as castling.
+///
+/// # Errors
+///
+/// Returns an error if the input string is not valid UCI notation, or if there is no piece on
+/// the source square.
+pub fn parse_uci_move(input: &str, board: &ChessBoard) -> Result<Turn, ChessParseError> {
+    let input = input.trim();
+    if input == "0000" {
+        return Ok(Turn::Null);
+    }
+    if let Some(c) = input
+        .chars()
+        .find(|&c| !"abcdefgh12345678qrbnQRBN".contains(c))
+    {
+        return Err(ChessParseError {
+            character: c,
+            kind: ParseErrorKind::InvalidChars,
+        });
+    }
+    let square_chars = input.chars().take(4).collect::<String>();
+    if square_chars.len() < 4 {
+        return Err(ChessParseError {
+            character: ' ',
+            kind: ParseErrorKind::NeedSquare,
+        });
+    }
+    let src: Square = square_chars[0..2].parse().map_err(|e| ChessParseError {
+        character: square_chars[0..=0].chars().next().unwrap(),
+        kind: ParseErrorKind::ConversionError(e),
+    })?;
+    let dst: Square = square_chars[2..4].parse().map_err(|e| ChessParseError {
+        character: square_chars[2..=2].chars().next().unwrap(),
+        kind: ParseErrorKind::ConversionError(e),
+    })?;
+    let mut promotion_chars = input.chars().skip(4);
+    let promotion = match promotion_chars.next() {
+        Some(c) => Some(
+            c.to_ascii_uppercase()
+                .try_into()
+                .map_err(|e| ChessParseError {
+                    character: c,
+                    kind: ParseErrorKind::ConversionError(e),
+                })?,
+        ),
+        None => None,
+    };
+    if let Some(c) = promotion_chars.next() {
+        return Err(ChessParseError {
+            character: c,
+            kind: ParseErrorKind::ExcessSquares,
+        });
+    }
+
+    let Some(&piece) = board.get(&src) else {
+        return Err(ChessParseError {
+            character: square_chars[0..=0].chars().next().unwrap(),
+            kind: ParseErrorKind::MissingPiece,
+        });
+    };
+    match Turn::new((src, piece), dst) {
+        turn @ (Turn::Castling(_, _) | Turn::Null) => Ok(turn),
+        Turn::Move(r#move) => verify_move(Move {
+            promotion,
+            ..r#move
+        })
+        .map_err(|e| ChessParseError {
+            character: input.chars().last().expect("input has characters"),
+            kind: ParseErrorKind::PromotionError(e),
+        }),
+    }
+}
+
 fn verify_move(r#move: Move) -> Result<Turn, PromotionError> {
     if let Some(piece) = r#move.promotion {
         if let PieceType::King | PieceType::Pawn = piece {
@@ -233,6 +395,27 @@ fn get_squares(turn: &str) -> Result<(Square, Option<Source>), ChessParseError>
     }
 }
 
+fn verify_dash(turn: &str) -> Result<(), ChessParseError> {
+    let Some(idx) = turn.find('-') else {
+        return Ok(());
+    };
+    let is_square = |s: &str| {
+        let mut chars = s.chars();
+        matches!(
+            (chars.next(), chars.next()),
+            (Some('a'..='h'), Some('1'..='8'))
+        )
+    };
+    if idx >= 2 && is_square(&turn[idx - 2..]) && is_square(&turn[idx + 1..]) {
+        Ok(())
+    } else {
+        Err(ChessParseError {
+            character: '-',
+            kind: ParseErrorKind::InvalidChars,
+        })
+    }
+}
+
 fn get_flags(turn: &str) -> u8 {
     let mut flag = flags::NONE;
     if turn.contains('x') {
@@ -276,6 +459,75 @@ mod tests {
         });
     }
     #[test]
+    fn en_passant_marker() {
+        let capture = parse_move("exd6").unwrap();
+        assert_eq!(parse_move("exd6e.p.").unwrap(), capture);
+        assert_eq!(parse_move("exd6 e.p.").unwrap(), capture);
+        assert_eq!(parse_move("exd6 E.P.").unwrap(), capture);
+        assert_eq!(
+            parse_move("exd6+ e.p.").unwrap(),
+            parse_move("exd6+").unwrap()
+        );
+        assert_eq!(
+            parse_move_localized("exd6 e.p.", PieceLanguage::German).unwrap(),
+            capture
+        );
+        assert!(parse_move("e.p.").is_err());
+    }
+    #[test]
+    fn null_move() {
+        use alloc::string::ToString;
+        assert_eq!(parse_move("--").unwrap(), Turn::Null);
+        assert_eq!(parse_move("Z0").unwrap(), Turn::Null);
+        assert_eq!(Turn::Null.to_string(), "--");
+        let board = ChessBoard::default();
+        assert_eq!(parse_uci_move("0000", &board).unwrap(), Turn::Null);
+        assert_eq!(Turn::Null.to_uci(&board), "0000");
+    }
+    #[test]
+    fn localized_pieces() {
+        let knight = parse_move("Nf3").unwrap();
+        assert_eq!(
+            parse_move_localized("Sf3", PieceLanguage::German).unwrap(),
+            knight
+        );
+        assert_eq!(
+            parse_move_localized("Cf3", PieceLanguage::French).unwrap(),
+            knight
+        );
+        assert_eq!(
+            parse_move_localized("Cf3", PieceLanguage::Spanish).unwrap(),
+            knight
+        );
+        assert_eq!(
+            parse_move_localized("Nf3", PieceLanguage::English).unwrap(),
+            knight
+        );
+        assert_eq!(
+            parse_move_localized("Txe1+", PieceLanguage::German).unwrap(),
+            parse_move("Rxe1+").unwrap()
+        );
+        assert_eq!(
+            parse_move_localized("Rf1", PieceLanguage::French).unwrap(),
+            parse_move("Kf1").unwrap()
+        );
+        assert_eq!(
+            parse_move_localized("e8=D", PieceLanguage::German).unwrap(),
+            parse_move("e8=Q").unwrap()
+        );
+        assert_eq!(
+            parse_move_localized("O-O", PieceLanguage::French).unwrap(),
+            parse_move("O-O").unwrap()
+        );
+        assert!(matches!(
+            parse_move_localized("Nf3", PieceLanguage::German),
+            Err(ChessParseError {
+                character: 'N',
+                kind: ParseErrorKind::InvalidChars
+            })
+        ));
+    }
+    #[test]
     fn castling() {
         assert!(
             if let Ok(Turn::Castling(CastlingType::Short, flags::NONE)) = parse_move("O-O") {
@@ -323,6 +575,63 @@ mod tests {
         })
     }
     #[test]
+    fn coordinate_dash() {
+        assert!(matches!(
+            parse_move("e2-e4"),
+            Ok(Turn::Move(Move {
+                piece: PieceType::Pawn,
+                dst: Square::E4,
+                flags: flags::NONE,
+                src: Some(Source::Square(Square::E2)),
+                promotion: None,
+            }))
+        ));
+        assert!(matches!(
+            parse_move("e4-e5"),
+            Ok(Turn::Move(Move {
+                piece: PieceType::Pawn,
+                dst: Square::E5,
+                flags: flags::NONE,
+                src: Some(Source::Square(Square::E4)),
+                promotion: None,
+            }))
+        ));
+        assert!(matches!(
+            parse_move("e4-d5"),
+            Ok(Turn::Move(Move {
+                piece: PieceType::Pawn,
+                dst: Square::D5,
+                flags: flags::NONE,
+                src: Some(Source::Square(Square::E4)),
+                promotion: None,
+            }))
+        ));
+        assert!(matches!(
+            parse_move("Ng1-f3"),
+            Ok(Turn::Move(Move {
+                piece: PieceType::Knight,
+                dst: Square::F3,
+                flags: flags::NONE,
+                src: Some(Source::Square(Square::G1)),
+                promotion: None,
+            }))
+        ));
+        assert!(matches!(
+            parse_move("e7-e8=Q"),
+            Ok(Turn::Move(Move {
+                piece: PieceType::Pawn,
+                dst: Square::E8,
+                src: Some(Source::Square(Square::E7)),
+                promotion: Some(PieceType::Queen),
+                ..
+            }))
+        ));
+        assert!(parse_move("e-e4").is_err());
+        assert!(parse_move("e4-").is_err());
+        assert!(parse_move("-e4").is_err());
+        assert!(parse_move("Nf3-").is_err());
+    }
+    #[test]
     fn promotion() {
         assert!(if let Ok(Turn::Move(Move {
             piece: PieceType::Pawn,
@@ -371,6 +680,70 @@ mod tests {
         assert!(parse_move("a8").is_err());
     }
 
+    #[test]
+    fn uci_moves() {
+        let board = ChessBoard::default();
+        assert!(matches!(
+            parse_uci_move("e2e4", &board),
+            Ok(Turn::Move(Move {
+                piece: PieceType::Pawn,
+                dst: Square::E4,
+                flags: flags::NONE,
+                src: Some(Source::Square(Square::E2)),
+                promotion: None,
+            }))
+        ));
+        assert!(matches!(
+            parse_uci_move("g1f3", &board),
+            Ok(Turn::Move(Move {
+                piece: PieceType::Knight,
+                dst: Square::F3,
+                src: Some(Source::Square(Square::G1)),
+                ..
+            }))
+        ));
+        assert!(matches!(
+            parse_uci_move("e2e", &board),
+            Err(ChessParseError {
+                kind: ParseErrorKind::NeedSquare,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse_uci_move("e4e5", &board),
+            Err(ChessParseError {
+                kind: ParseErrorKind::MissingPiece,
+                ..
+            })
+        ));
+
+        let board = "4k3/4P3/8/8/8/8/8/R3K2R w KQ - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        for input in ["e7e8q", "e7e8Q"] {
+            assert!(matches!(
+                parse_uci_move(input, &board),
+                Ok(Turn::Move(Move {
+                    piece: PieceType::Pawn,
+                    dst: Square::E8,
+                    src: Some(Source::Square(Square::E7)),
+                    promotion: Some(PieceType::Queen),
+                    ..
+                }))
+            ));
+        }
+        assert!(parse_uci_move("e7e8", &board).is_err());
+        assert!(parse_uci_move("e7e8k", &board).is_err());
+        assert!(matches!(
+            parse_uci_move("e1g1", &board),
+            Ok(Turn::Castling(CastlingType::Short, flags::NONE))
+        ));
+        assert!(matches!(
+            parse_uci_move("e1c1", &board),
+            Ok(Turn::Castling(CastlingType::Long, flags::NONE))
+        ));
+    }
+
     #[test]
     fn invalid_input() {
         assert!(parse_move("sljfelk0932").is_err());
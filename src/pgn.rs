@@ -1,37 +1,260 @@
-use crate::Turn;
+use crate::{parser::ChessParseError, Turn};
 use std::collections::HashMap;
+use std::io::{self, BufRead, Lines};
+use std::iter::Peekable;
+
+/// The tags and moves of a pgn game, or the index of the first move that could not be parsed
+/// along with its parse error
+pub type PgnResult = Result<(HashMap<String, String>, Vec<Turn>), (usize, ChessParseError)>;
 
 /// Takes in a pgn string and returns the game data
-pub fn read_pgn(pgn_string: &str) -> (HashMap<String, String>, Vec<Turn>) {
+///
+/// Move numbers, comments, variations, NAGs and the game result are skipped
+///
+/// # Errors
+///
+/// Returns the index of the first move that could not be parsed, along with its parse error
+pub fn read_pgn(pgn_string: &str) -> PgnResult {
+    let (info, moves) = split_pgn_string(pgn_string);
+    Ok((parse_pgn_info(&info), parse_pgn_moves(&moves)?))
+}
+
+/// Same as [read_pgn], but first prints the tag section and the movetext to stdout
+///
+/// # Errors
+///
+/// Returns the index of the first move that could not be parsed, along with its parse error
+pub fn read_pgn_verbose(pgn_string: &str) -> PgnResult {
     let (info, moves) = split_pgn_string(pgn_string);
     println!("{info}");
     println!("{moves}");
-    (parse_pgn_info(&info), parse_pgn_moves(&moves))
+    read_pgn(pgn_string)
+}
+
+/// A pgn game with its tags, main line, variations, comments and NAGs
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PgnGame {
+    /// The tag pairs in the pgn header
+    pub tags: HashMap<String, String>,
+    /// The comment that comes before the first move of the game
+    pub comment: Option<String>,
+    /// The moves of the main line
+    pub main_line: Vec<PgnNode>,
+    /// The game termination marker at the end of the movetext, e.g. `1-0` or `*`
+    pub result: Option<String>,
+}
+
+/// A single move in a pgn game, with the annotations that follow it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnNode {
+    /// The move that was played
+    pub turn: Turn,
+    /// The comment that follows the move
+    pub comment: Option<String>,
+    /// The numeric annotation glyphs of the move, with the suffix annotations `!`, `?`, `!!`,
+    /// `??`, `!?` and `?!` converted to their NAG codes
+    pub nags: Vec<u8>,
+    /// The alternatives to this move, each one a line of moves
+    pub variations: Vec<Vec<PgnNode>>,
+}
+
+impl PgnGame {
+    /// Returns the turns of the main line
+    pub fn turns(&self) -> Vec<Turn> {
+        self.main_line.iter().map(|node| node.turn).collect()
+    }
+}
+
+/// Takes in a pgn string and returns the game data with its variations, comments and NAGs
+///
+/// Comments are attached to the move they follow, and a comment before the first move of a
+/// variation is attached to that move. Tokens that are not valid moves are skipped
+pub fn read_pgn_tree(pgn_string: &str) -> PgnGame {
+    let (info, moves) = split_pgn_string(pgn_string);
+    let mut game = PgnGame {
+        tags: parse_pgn_info(&info),
+        ..Default::default()
+    };
+    // each open line holds its moves and the comment that came before its first move
+    let mut lines: Vec<(Vec<PgnNode>, Option<String>)> = vec![(Vec::new(), None)];
+    let mut chars = moves.chars().peekable();
+    while let Some(ch) = chars.next() {
+        let line = lines.last_mut().expect("the main line is never popped");
+        match ch {
+            '{' | ';' => {
+                let close = if ch == '{' { '}' } else { '\n' };
+                let comment = chars
+                    .by_ref()
+                    .take_while(|&ch| ch != close)
+                    .collect::<String>();
+                let comment = comment.split_whitespace().collect::<Vec<_>>().join(" ");
+                let target = match line.0.last_mut() {
+                    Some(node) => &mut node.comment,
+                    None => &mut line.1,
+                };
+                push_comment(target, comment);
+            }
+            '(' => lines.push((Vec::new(), None)),
+            ')' => {
+                if lines.len() > 1 {
+                    let variation = close_line(lines.pop().expect("there are at least two lines"));
+                    if let Some(node) = lines.last_mut().and_then(|line| line.0.last_mut()) {
+                        node.variations.push(variation);
+                    }
+                }
+            }
+            '$' => {
+                let mut nag = String::new();
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    nag.push(digit);
+                }
+                if let (Ok(nag), Some(node)) = (nag.parse(), line.0.last_mut()) {
+                    node.nags.push(nag);
+                }
+            }
+            ch if ch.is_whitespace() => {}
+            ch => {
+                let mut token = String::from(ch);
+                while let Some(ch) =
+                    chars.next_if(|&ch| !ch.is_whitespace() && !"{;()$".contains(ch))
+                {
+                    token.push(ch);
+                }
+                if matches!(token.as_str(), "1-0" | "0-1" | "1/2-1/2" | "*") {
+                    game.result = Some(token);
+                    continue;
+                }
+                let token = strip_move_number(&token);
+                let suffix_start = token.find(['!', '?']).unwrap_or(token.len());
+                let (turn, suffix) = token.split_at(suffix_start);
+                if let Ok(turn) = turn.parse::<Turn>() {
+                    line.0.push(PgnNode {
+                        turn,
+                        comment: None,
+                        nags: suffix_nag(suffix).into_iter().collect(),
+                        variations: Vec::new(),
+                    });
+                }
+            }
+        }
+    }
+    while lines.len() > 1 {
+        let variation = close_line(lines.pop().expect("there are at least two lines"));
+        if let Some(node) = lines.last_mut().and_then(|line| line.0.last_mut()) {
+            node.variations.push(variation);
+        }
+    }
+    let (main_line, comment) = lines.pop().expect("the main line is never popped");
+    game.main_line = main_line;
+    game.comment = comment;
+    game
 }
 
-/// Takes in a list of pgns separated by empty lines and splits them into their respective data
-pub fn read_pgn_list(pgn_list_string: &str) -> Vec<(HashMap<String, String>, Vec<Turn>)> {
-    split_pgn_list(pgn_list_string)
-        .into_iter()
-        .map(|pgn_string| read_pgn(&pgn_string))
+/// Takes in a list of pgns and splits them into their respective data
+pub fn read_pgn_list(pgn_list_string: &str) -> Vec<PgnResult> {
+    PgnReader::new(pgn_list_string.as_bytes())
+        .map(|pgn| pgn.expect("reading from a string can't fail"))
         .collect()
 }
 
+/// An iterator over the games of a pgn database, reading one game at a time
+///
+/// A game ends at its result token, or at the tag section of the next game when the result is
+/// missing, so blank lines inside the tag section or the movetext are allowed. A read error is
+/// yielded in place of the game it interrupts, and reading continues after the failed line
+pub struct PgnReader<R: BufRead> {
+    lines: Peekable<Lines<R>>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    /// Creates a reader over the games in `reader`
+    pub fn new(reader: R) -> PgnReader<R> {
+        PgnReader {
+            lines: reader.lines().peekable(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = io::Result<PgnResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_pgn_string()
+            .map(|pgn_string| pgn_string.map(|pgn_string| read_pgn(&pgn_string)))
+    }
+}
+
+impl<R: BufRead> PgnReader<R> {
+    /// Reads the text of the next game, or returns `None` once the reader is exhausted
+    fn next_pgn_string(&mut self) -> Option<io::Result<String>> {
+        let mut pgn_string = String::new();
+        let mut in_movetext = false;
+        let mut in_comment = false;
+        loop {
+            let line = match self.lines.peek() {
+                None => break,
+                Some(Ok(line)) => line.trim_end(),
+                Some(Err(_)) => {
+                    let err = self.lines.next()?.expect_err("the peeked line is an error");
+                    return Some(Err(err));
+                }
+            };
+            if !in_movetext {
+                if line.is_empty() {
+                    self.lines.next();
+                    continue;
+                }
+                if !line.starts_with('[') {
+                    in_movetext = true;
+                    pgn_string.push('\n');
+                }
+            } else if line.starts_with('[') && !in_comment {
+                break;
+            }
+            pgn_string.push_str(line);
+            pgn_string.push('\n');
+            let ends_game = in_movetext && ends_with_result(line, &mut in_comment);
+            self.lines.next();
+            if ends_game {
+                break;
+            }
+        }
+        if pgn_string.trim().is_empty() {
+            None
+        } else {
+            Some(Ok(pgn_string))
+        }
+    }
+}
+
+/// Returns whether the last token of a movetext line outside of comments is a game result,
+/// tracking whether a `{` comment continues onto the next line
+fn ends_with_result(line: &str, in_comment: &mut bool) -> bool {
+    let mut outside = String::new();
+    for ch in line.chars() {
+        match ch {
+            '{' if !*in_comment => *in_comment = true,
+            '}' if *in_comment => {
+                *in_comment = false;
+                outside.push(' ');
+            }
+            ';' if !*in_comment => break,
+            ch if !*in_comment => outside.push(ch),
+            _ => {}
+        }
+    }
+    matches!(
+        outside.split_whitespace().next_back(),
+        Some("1-0" | "0-1" | "1/2-1/2" | "*")
+    )
+}
+
 /// Takes in a pgn string and returns the game result that is appended to the end of the move
 /// sequence
 pub fn get_game_result(pgn_string: &str) -> Option<&str> {
     pgn_string.split_whitespace().last()
 }
 
-fn split_pgn_list(pgn_list_string: &str) -> Vec<String> {
-    pgn_list_string
-        .split("\n\n")
-        .collect::<Vec<_>>()
-        .chunks(2)
-        .map(|items| items.join("\n"))
-        .collect()
-}
-
 fn split_pgn_string(pgn_string: &str) -> (String, String) {
     let info = pgn_string
         .lines()
@@ -71,52 +294,90 @@ fn parse_pgn_info(info_string: &str) -> HashMap<String, String> {
     info
 }
 
-fn parse_pgn_moves(moves_string: &str) -> Vec<Turn> {
-    moves_string
-        .lines()
-        .skip_while(|line| line.starts_with('[') || line.is_empty())
-        .fold(String::new(), |s, l| s + " " + l)
-        .chars()
-        .scan(false, scan_between('{', '}', false))
-        .filter(|&ch| ch != '�')
-        .collect::<String>()
-        .split_whitespace()
-        .map(|substr| {
-            substr
-                .split('.')
-                .last()
-                .expect("split always produces an iterator")
-        })
-        .filter_map(|turn| turn.parse::<Turn>().ok())
-        .collect()
+fn parse_pgn_moves(moves_string: &str) -> Result<Vec<Turn>, (usize, ChessParseError)> {
+    let mut turns = Vec::new();
+    let mut variation_depth = 0usize;
+    let mut chars = moves_string.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' => {
+                chars.by_ref().find(|&ch| ch == '}');
+            }
+            ';' => {
+                chars.by_ref().find(|&ch| ch == '\n');
+            }
+            '(' => variation_depth += 1,
+            ')' => variation_depth = variation_depth.saturating_sub(1),
+            '$' => while chars.next_if(char::is_ascii_digit).is_some() {},
+            ch if ch.is_whitespace() => {}
+            ch => {
+                let mut token = String::from(ch);
+                while let Some(ch) =
+                    chars.next_if(|&ch| !ch.is_whitespace() && !"{;()$".contains(ch))
+                {
+                    token.push(ch);
+                }
+                if variation_depth > 0
+                    || matches!(token.as_str(), "1-0" | "0-1" | "1/2-1/2" | "*")
+                    || token.eq_ignore_ascii_case("e.p.")
+                {
+                    continue;
+                }
+                let turn = strip_move_number(&token).trim_end_matches(['!', '?']);
+                if turn.is_empty() {
+                    continue;
+                }
+                turns.push(turn.parse().map_err(|err| (turns.len(), err))?);
+            }
+        }
+    }
+    Ok(turns)
 }
 
-fn scan_between(
-    open_delimiter: char,
-    close_delimiter: char,
-    keep: bool,
-) -> impl Fn(&mut bool, char) -> Option<char> {
-    move |is_between: &mut bool, ch: char| {
-        if open_delimiter == close_delimiter && ch == open_delimiter {
-            *is_between = !*is_between;
-            Some('�')
-        } else if ch == open_delimiter {
-            *is_between = true;
-            Some('�')
-        } else if ch == close_delimiter {
-            *is_between = false;
-            Some('�')
-        } else if *is_between {
-            if keep {
-                Some(ch)
-            } else {
-                Some('�')
-            }
-        } else if keep {
-            Some('�')
-        } else {
-            Some(ch)
+/// Strips a leading move number such as `12.` or `12...` from a token
+fn strip_move_number(token: &str) -> &str {
+    let rest = token.trim_start_matches(|ch: char| ch.is_ascii_digit());
+    if rest.len() < token.len() && rest.starts_with('.') {
+        rest.trim_start_matches('.')
+    } else {
+        token
+    }
+}
+
+fn push_comment(target: &mut Option<String>, comment: String) {
+    if comment.is_empty() {
+        return;
+    }
+    match target {
+        Some(existing) => {
+            existing.push(' ');
+            existing.push_str(&comment);
         }
+        None => *target = Some(comment),
+    }
+}
+
+/// Moves the comment before the first move of a variation onto that move
+fn close_line((mut nodes, comment): (Vec<PgnNode>, Option<String>)) -> Vec<PgnNode> {
+    if let (Some(comment), Some(first)) = (comment, nodes.first_mut()) {
+        let mut combined = Some(comment);
+        if let Some(existing) = first.comment.take() {
+            push_comment(&mut combined, existing);
+        }
+        first.comment = combined;
+    }
+    nodes
+}
+
+fn suffix_nag(suffix: &str) -> Option<u8> {
+    match suffix {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
     }
 }
 
@@ -128,12 +389,44 @@ mod test {
         ChessGame, GameState,
     };
 
+    #[test]
+    fn read_pgn_stdout() {
+        // the harness captures the output of a test, so the pgn is read in a child process
+        // running only this test, and its stdout is checked instead
+        const PGN: &str = "[Event \"Stdout Check\"]\n\n1. e4 e5 *";
+        if let Ok(verbose) = std::env::var("CHESS_READ_PGN_CHILD") {
+            let read = if verbose == "1" {
+                read_pgn_verbose(PGN)
+            } else {
+                read_pgn(PGN)
+            };
+            assert_eq!(read.unwrap().1.len(), 2);
+            return;
+        }
+        let child_stdout = |verbose: &str| {
+            let output = std::process::Command::new(std::env::current_exe().unwrap())
+                .args(["pgn::test::read_pgn_stdout", "--exact", "--nocapture"])
+                .env("CHESS_READ_PGN_CHILD", verbose)
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+        assert!(!child_stdout("0").contains("Stdout Check"));
+        assert!(child_stdout("1").contains("Stdout Check"));
+    }
     #[test]
     fn pgn_list_read() -> Result<(), Box<dyn std::error::Error>> {
         let pgn_string_list = std::fs::read_to_string("res/pgn_list.pgn")?;
 
-        for pgn_string in split_pgn_list(&pgn_string_list) {
-            play_game(read_pgn(&pgn_string), get_game_result(&pgn_string))?
+        assert_eq!(read_pgn_list(&pgn_string_list).len(), 39);
+        let mut reader = PgnReader::new(pgn_string_list.as_bytes());
+        while let Some(pgn_string) = reader.next_pgn_string() {
+            let pgn_string = pgn_string?;
+            play_game(
+                read_pgn(&pgn_string).map_err(|(_, err)| err)?,
+                get_game_result(&pgn_string),
+            )?
         }
 
         Ok(())
@@ -142,7 +435,120 @@ mod test {
     fn pgn_single_read() -> Result<(), Box<dyn std::error::Error>> {
         let pgn_string = std::fs::read_to_string("res/test.pgn")?;
 
-        play_game(read_pgn(&pgn_string), get_game_result(&pgn_string))
+        play_game(
+            read_pgn(&pgn_string).map_err(|(_, err)| err)?,
+            get_game_result(&pgn_string),
+        )
+    }
+
+    #[test]
+    fn pgn_reader_streams_games() -> Result<(), Box<dyn std::error::Error>> {
+        let file = std::fs::File::open("res/pgn_list.pgn")?;
+        let mut reader = PgnReader::new(std::io::BufReader::new(file));
+        let (info, _) = reader.next().unwrap()?.map_err(|(_, err)| err)?;
+        assert_eq!(info.get("Event").map(String::as_str), Some("London "));
+        assert_eq!(reader.count(), 38);
+        Ok(())
+    }
+    #[test]
+    fn pgn_reader_boundaries() {
+        let pgn_list = "\n[Event \"First\"]\n\n[Result \"1-0\"]\n\n1. e4 e5\n\n2. Qh5 Nc6 \
+            {not over: 0-1\n1/2-1/2} 3. Bc4 Nf6 4. Qxf7# 1-0\n\
+            [Event \"Second\"]\n\n1. d4 d5\n\
+            [Event \"Third\"]\n\n1. c4 *\n\n";
+        let games = PgnReader::new(pgn_list.as_bytes())
+            .map(|pgn| pgn.unwrap().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(games.len(), 3);
+        assert_eq!(games[0].0.get("Result").map(String::as_str), Some("1-0"));
+        assert_eq!(games[0].1.len(), 7);
+        assert_eq!(games[1].0.get("Event").map(String::as_str), Some("Second"));
+        assert_eq!(games[1].1.len(), 2);
+        assert_eq!(games[2].1.len(), 1);
+    }
+    #[test]
+    fn pgn_reader_read_error() {
+        let pgn_list: &[u8] =
+            b"[Event \"First\"]\n\n1. e4 *\n\xff\n[Event \"Second\"]\n\n1. d4 *\n";
+        let mut reader = PgnReader::new(pgn_list);
+        assert!(matches!(reader.next(), Some(Ok(Ok(_)))));
+        assert_eq!(
+            reader.next().unwrap().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        let (info, _) = reader.next().unwrap().unwrap().unwrap();
+        assert_eq!(info.get("Event").map(String::as_str), Some("Second"));
+        assert!(reader.next().is_none());
+    }
+    #[test]
+    fn pgn_black_move_numbers() {
+        let (_, moves) =
+            read_pgn("[Event \"Test\"]\n\n12... Qxd4 13.Nxd4 13...e5 14. Nf3 *").unwrap();
+        assert_eq!(
+            moves,
+            ["Qxd4", "Nxd4", "e5", "Nf3"].map(|turn| turn.parse::<Turn>().unwrap())
+        );
+    }
+    #[test]
+    fn pgn_interleaved_annotations() {
+        let pgn_string = "1. e4! {Best by test} $1 e5?! (1... c5 {Sicilian} 2. Nf3) 2. Nf3 \
+            ; King's knight\nNc6 $6 3. Bb5 1-0";
+        let (_, moves) = read_pgn(pgn_string).unwrap();
+        assert_eq!(
+            moves,
+            ["e4", "e5", "Nf3", "Nc6", "Bb5"].map(|turn| turn.parse::<Turn>().unwrap())
+        );
+    }
+    #[test]
+    fn pgn_unparsable_move() {
+        assert!(matches!(read_pgn("1. e4 e5 2. Zz9 Nc6 *"), Err((2, _))));
+    }
+    #[test]
+    fn pgn_tree_read() {
+        let pgn_string = "[Event \"Test\"]\n[Result \"*\"]\n\n{Opening} 1.e4 $1 {Best by test} e5 \
+            (1...c5 {Sicilian} 2.Nf3 (2.c3 d5) ({Closed} 2.Nc3) 2...d6) (1...e6?!) \
+            2.Nf3!? ; King's knight\nNc6 *";
+        let game = read_pgn_tree(pgn_string);
+
+        assert_eq!(game.tags.get("Event").map(String::as_str), Some("Test"));
+        assert_eq!(game.comment.as_deref(), Some("Opening"));
+        assert_eq!(game.result.as_deref(), Some("*"));
+        assert_eq!(
+            game.turns(),
+            ["e4", "e5", "Nf3", "Nc6"].map(|turn| turn.parse::<Turn>().unwrap())
+        );
+
+        let e4 = &game.main_line[0];
+        assert_eq!(e4.nags, [1]);
+        assert_eq!(e4.comment.as_deref(), Some("Best by test"));
+
+        let e5 = &game.main_line[1];
+        assert_eq!(e5.variations.len(), 2);
+        let sicilian = &e5.variations[0];
+        assert_eq!(sicilian.len(), 3);
+        assert_eq!(sicilian[0].comment.as_deref(), Some("Sicilian"));
+        assert_eq!(sicilian[1].variations.len(), 2);
+        assert_eq!(sicilian[1].variations[0].len(), 2);
+        assert_eq!(
+            sicilian[1].variations[1][0].comment.as_deref(),
+            Some("Closed")
+        );
+        assert_eq!(e5.variations[1][0].nags, [6]);
+
+        let nf3 = &game.main_line[2];
+        assert_eq!(nf3.nags, [5]);
+        assert_eq!(nf3.comment.as_deref(), Some("King's knight"));
+    }
+    #[test]
+    fn pgn_tree_matches_flat_read() -> Result<(), Box<dyn std::error::Error>> {
+        let pgn_string = std::fs::read_to_string("res/test.pgn")?;
+        let game = read_pgn_tree(&pgn_string);
+        let (info, moves) = read_pgn(&pgn_string).map_err(|(_, err)| err)?;
+
+        assert_eq!(game.tags, info);
+        assert_eq!(game.turns(), moves);
+        assert_eq!(game.result.as_deref(), Some("1-0"));
+        Ok(())
     }
 
     fn play_game(
@@ -164,14 +570,10 @@ mod test {
         //    game.players.1 = black.clone();
         //}
         let move_read_result = (|| -> Result<GameState, Box<dyn std::error::Error>> {
-            for r#move in moves {
-                println!("{}", game.board_string());
-                println!("{move}");
-                game.make_move(&r#move)?;
-            }
+            game.play_turns(&moves).map_err(|(_, e)| e)?;
 
             match game_result.as_str() {
-                "1/2-1/2" => Ok(GameState::Draw(DrawType::Offer)),
+                "1/2-1/2" => Ok(GameState::Draw(DrawType::Agreement)),
                 "1-0" => Ok(GameState::Win(Win {
                     is_white: true,
                     kind: WinType::Resign,
@@ -1,21 +1,90 @@
 mod piece_type;
 pub use piece_type::PieceType;
 
-use std::fmt;
+use alloc::string::ToString;
+use core::fmt;
 
-#[derive(Clone, Debug, PartialEq, Copy)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The color of a player or piece
+pub enum Color {
+    /// The white player
+    White,
+    /// The black player
+    Black,
+}
+
+impl Color {
+    /// Returns the other color
+    pub fn opposite(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+    /// Returns whether the color is white
+    pub fn is_white(self) -> bool {
+        self == Color::White
+    }
+}
+
+impl From<bool> for Color {
+    /// Converts `true` to white and `false` to black
+    fn from(is_white: bool) -> Self {
+        if is_white {
+            Color::White
+        } else {
+            Color::Black
+        }
+    }
+}
+
+impl From<Color> for bool {
+    /// Converts white to `true` and black to `false`
+    fn from(color: Color) -> Self {
+        color.is_white()
+    }
+}
+
+impl core::ops::Not for Color {
+    type Output = Color;
+
+    fn not(self) -> Self::Output {
+        self.opposite()
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Color::White => write!(f, "White"),
+            Color::Black => write!(f, "Black"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A chess piece
 pub struct Piece {
     /// The type of piece
     pub piece: PieceType,
-    /// Whether the piece is owned by the white player or not
-    pub is_white: bool,
+    /// The player that owns the piece
+    pub color: Color,
 }
 
 impl Piece {
     /// Creates a new chess piece from its type and its owner
-    pub fn new(piece: PieceType, is_white: bool) -> Piece {
-        Piece { piece, is_white }
+    pub fn new(piece: PieceType, color: impl Into<Color>) -> Piece {
+        Piece {
+            piece,
+            color: color.into(),
+        }
+    }
+    /// Returns whether the piece is owned by the white player
+    #[deprecated(note = "use `Piece::color` instead")]
+    pub fn is_white(&self) -> bool {
+        self.color.is_white()
     }
 }
 
@@ -25,66 +94,66 @@ impl fmt::Display for Piece {
             match self {
                 Self {
                     piece: PieceType::King,
-                    is_white: true,
+                    color: Color::White,
                     ..
                 } => '\u{2654}',
                 Self {
                     piece: PieceType::Queen,
-                    is_white: true,
+                    color: Color::White,
                     ..
                 } => '\u{2655}',
                 Self {
                     piece: PieceType::Rook,
-                    is_white: true,
+                    color: Color::White,
                     ..
                 } => '\u{2656}',
                 Self {
                     piece: PieceType::Bishop,
-                    is_white: true,
+                    color: Color::White,
                     ..
                 } => '\u{2657}',
                 Self {
                     piece: PieceType::Knight,
-                    is_white: true,
+                    color: Color::White,
                     ..
                 } => '\u{2658}',
                 Self {
                     piece: PieceType::Pawn,
-                    is_white: true,
+                    color: Color::White,
                     ..
                 } => '\u{2659}',
                 Self {
                     piece: PieceType::King,
-                    is_white: false,
+                    color: Color::Black,
                     ..
                 } => '\u{265A}',
                 Self {
                     piece: PieceType::Queen,
-                    is_white: false,
+                    color: Color::Black,
                     ..
                 } => '\u{265B}',
                 Self {
                     piece: PieceType::Rook,
-                    is_white: false,
+                    color: Color::Black,
                     ..
                 } => '\u{265C}',
                 Self {
                     piece: PieceType::Bishop,
-                    is_white: false,
+                    color: Color::Black,
                     ..
                 } => '\u{265D}',
                 Self {
                     piece: PieceType::Knight,
-                    is_white: false,
+                    color: Color::Black,
                     ..
                 } => '\u{265E}',
                 Self {
                     piece: PieceType::Pawn,
-                    is_white: false,
+                    color: Color::Black,
                     ..
                 } => '\u{265F}',
             }
-        } else if self.is_white {
+        } else if self.color.is_white() {
             self.piece.to_string().chars().next().unwrap()
         } else {
             self.piece
@@ -96,3 +165,34 @@ impl fmt::Display for Piece {
         write!(f, "{}", chess_sym)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_conversions() {
+        assert_eq!(Color::White.opposite(), Color::Black);
+        assert_eq!(!Color::Black, Color::White);
+        assert_eq!(Color::from(true), Color::White);
+        assert!(!bool::from(Color::Black));
+        assert_eq!(Piece::new(PieceType::Pawn, false).color, Color::Black);
+        #[allow(deprecated)]
+        let is_white = Piece::new(PieceType::Pawn, true).is_white();
+        assert!(is_white);
+    }
+    #[test]
+    fn all_piece_types() {
+        for (i, piece) in PieceType::ALL.into_iter().enumerate() {
+            assert_eq!(piece.to_index(), i);
+            assert_eq!(PieceType::from_index(i), Some(piece));
+        }
+        assert_eq!(PieceType::from_index(6), None);
+    }
+    #[test]
+    fn promotable_piece_types() {
+        assert!(!PieceType::PROMOTABLE.contains(&PieceType::King));
+        assert!(!PieceType::PROMOTABLE.contains(&PieceType::Pawn));
+        assert_eq!(PieceType::PROMOTABLE[0], PieceType::Queen);
+    }
+}
@@ -1,8 +1,10 @@
-use std::fmt;
-use std::str::FromStr;
+use alloc::string::ToString;
+use core::fmt;
+use core::str::FromStr;
 
 use crate::parser::ConversionError;
-#[derive(Clone, Debug, Copy, PartialEq)]
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 /// The type of a piece
 pub enum PieceType {
@@ -13,6 +15,38 @@ pub enum PieceType {
     Knight,
     Pawn,
 }
+impl PieceType {
+    /// Every type of piece, in the order that they are declared
+    pub const ALL: [PieceType; 6] = [
+        Self::King,
+        Self::Queen,
+        Self::Rook,
+        Self::Bishop,
+        Self::Knight,
+        Self::Pawn,
+    ];
+    /// The types that a pawn can promote to, from the most to the least valuable
+    pub const PROMOTABLE: [PieceType; 4] = [Self::Queen, Self::Rook, Self::Bishop, Self::Knight];
+    /// Returns the piece type at the given index of [PieceType::ALL], returns `None` if the
+    /// index is out of range
+    pub fn from_index(index: usize) -> Option<PieceType> {
+        Self::ALL.get(index).copied()
+    }
+    /// Returns the index of the piece type in [PieceType::ALL]
+    pub fn to_index(self) -> usize {
+        self as usize
+    }
+    /// Returns the standard material value of the piece, where the king has no value
+    pub fn value(&self) -> i32 {
+        match self {
+            Self::King => 0,
+            Self::Queen => 9,
+            Self::Rook => 5,
+            Self::Bishop | Self::Knight => 3,
+            Self::Pawn => 1,
+        }
+    }
+}
 impl fmt::Display for PieceType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let piece_letter = match self {
@@ -1,24 +1,31 @@
 mod castling;
 mod r#move;
 
-use std::{fmt::Display, str::FromStr};
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+use core::{fmt::Display, str::FromStr};
 
 pub use castling::CastlingType;
 pub use r#move::Move;
 
 use crate::{
-    board::{Source, Square},
+    board::{ChessBoard, Source, Square},
     parser::{parse_move, ChessParseError},
     pieces::{Piece, PieceType},
 };
 
 #[derive(Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The type of turn
 pub enum Turn {
     /// A castling turn
     Castling(CastlingType, u8),
     /// A piece move turn
     Move(Move),
+    /// A null move, which passes the turn without moving a piece, written as `--`
+    Null,
 }
 impl Turn {
     /// Creates a new turn from a piece, its location, and the destination square.
@@ -45,6 +52,59 @@ impl Turn {
             })
         }
     }
+    /// Returns the turn with the provided promotion piece, leaving castling and null moves
+    /// unchanged
+    pub fn with_promotion(self, piece: PieceType) -> Turn {
+        match self {
+            Turn::Move(r#move) => Turn::Move(r#move.promote(piece)),
+            turn => turn,
+        }
+    }
+    /// Returns whether the turn is flagged as giving check, a checkmate is only flagged by
+    /// [Turn::is_checkmate]
+    pub fn is_check(&self) -> bool {
+        match self {
+            Turn::Castling(_, flags) => flags & flags::CHECK != 0,
+            Turn::Move(r#move) => r#move.is_check(),
+            Turn::Null => false,
+        }
+    }
+    /// Returns whether the turn is flagged as giving checkmate
+    pub fn is_checkmate(&self) -> bool {
+        match self {
+            Turn::Castling(_, flags) => flags & flags::CHECKMATE != 0,
+            Turn::Move(r#move) => r#move.is_checkmate(),
+            Turn::Null => false,
+        }
+    }
+    /// Returns the turn in UCI long algebraic notation, such as `e2e4` or `e7e8q`, or `0000` for
+    /// a null move
+    ///
+    /// The board is the position before the turn is made, and is used to determine which king
+    /// castles
+    ///
+    /// # Panics
+    ///
+    /// Panics if the turn is a move and does not have [Source::Square] as the source
+    pub fn to_uci(&self, board: &ChessBoard) -> String {
+        match self {
+            Turn::Castling(castling_type, _) => {
+                let ((src, dst), _) = board.castling_squares(board.side_to_move(), castling_type);
+                format!("{src}{dst}")
+            }
+            Turn::Move(r#move) => {
+                let Some(Source::Square(src)) = r#move.src else {
+                    panic!("No specified source");
+                };
+                let promotion = match r#move.promotion {
+                    Some(pc) => pc.to_string().to_lowercase(),
+                    None => "".to_string(),
+                };
+                format!("{src}{}{promotion}", r#move.dst)
+            }
+            Turn::Null => "0000".to_string(),
+        }
+    }
 }
 
 impl FromStr for Turn {
@@ -55,31 +115,39 @@ impl FromStr for Turn {
 }
 
 impl Display for Turn {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    /// Writes the turn in algebraic notation, the alternate form `{:#}` leaves out the capture
+    /// `x`, check `+`, and checkmate `#` markers
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let mut output = String::new();
+        // the alternate form leaves out the capture, check, and checkmate decorations
+        let decorated = !f.alternate();
+        let check_or_checkmate = if !decorated {
+            ""
+        } else if self.is_checkmate() {
+            "#"
+        } else if self.is_check() {
+            "+"
+        } else {
+            ""
+        };
         match self {
-            Turn::Castling(castling_type, flags) => {
+            Turn::Castling(castling_type, _) => {
                 let base = match castling_type {
                     CastlingType::Long => "0-0-0",
                     CastlingType::Short => "0-0",
                 };
-                let check_or_checkmate = if flags & flags::CHECKMATE != 0 {
-                    "#"
-                } else if flags & flags::CHECK != 0 {
-                    "+"
-                } else {
-                    ""
-                };
                 output.push_str(base);
                 output.push_str(check_or_checkmate);
             }
-            Turn::Move(Move {
-                piece,
-                dst,
-                flags,
-                src,
-                promotion,
-            }) => {
+            Turn::Move(
+                r#move @ Move {
+                    piece,
+                    dst,
+                    src,
+                    promotion,
+                    ..
+                },
+            ) => {
                 let piece = match piece {
                     PieceType::Pawn => "".to_string(),
                     _ => piece.to_string(),
@@ -88,28 +156,21 @@ impl Display for Turn {
                     Some(src) => src.to_string(),
                     None => "".to_string(),
                 };
-                let capture = flags & flags::CAPTURE != 0;
                 let promotion = match promotion {
                     Some(pc) => "=".to_string() + &pc.to_string(),
                     None => "".to_string(),
                 };
-                let check_or_checkmate = if flags & flags::CHECKMATE != 0 {
-                    "#"
-                } else if flags & flags::CHECK != 0 {
-                    "+"
-                } else {
-                    ""
-                };
 
                 output.push_str(&piece);
                 output.push_str(&source);
-                if capture {
+                if decorated && r#move.is_capture() {
                     output.push('x');
                 }
                 output.push_str(&dst.to_string());
                 output.push_str(&promotion);
                 output.push_str(check_or_checkmate);
             }
+            Turn::Null => output.push_str("--"),
         }
 
         write!(f, "{}", output)
@@ -127,3 +188,99 @@ pub mod flags {
     /// The move captured a piece
     pub const CAPTURE: u8 = 1 << 2;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Line;
+
+    fn complete(board: &ChessBoard, turn: &str) -> Turn {
+        board
+            .validate_and_complete_turn(turn.parse().unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn uci_output() {
+        let board = ChessBoard::default();
+        assert_eq!(complete(&board, "e4").to_uci(&board), "e2e4");
+        assert_eq!(complete(&board, "Nf3").to_uci(&board), "g1f3");
+
+        let board = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert_eq!(complete(&board, "exd5").to_uci(&board), "e4d5");
+
+        let board = "8/4P3/1k6/8/8/8/8/R3K2R w KQ - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert_eq!(complete(&board, "e8=Q").to_uci(&board), "e7e8q");
+        assert_eq!(complete(&board, "e8=N").to_uci(&board), "e7e8n");
+        assert_eq!(complete(&board, "O-O").to_uci(&board), "e1g1");
+        assert_eq!(complete(&board, "O-O-O").to_uci(&board), "e1c1");
+
+        let board = "r3k2r/8/8/8/8/8/8/4K3 b kq - 0 1"
+            .parse::<ChessBoard>()
+            .unwrap();
+        assert_eq!(complete(&board, "O-O").to_uci(&board), "e8g8");
+        assert_eq!(complete(&board, "O-O-O").to_uci(&board), "e8c8");
+    }
+    #[test]
+    fn flag_predicates() {
+        let turn = "exf8=R#".parse::<Turn>().unwrap();
+        let Turn::Move(r#move) = turn else {
+            panic!("Not a move");
+        };
+        assert!(r#move.is_capture());
+        assert!(r#move.is_checkmate());
+        assert!(r#move.is_promotion());
+        assert!(!r#move.is_check());
+        assert!(turn.is_checkmate());
+        assert!(!turn.is_check());
+
+        let Turn::Move(r#move) = "Nf3+".parse::<Turn>().unwrap() else {
+            panic!("Not a move");
+        };
+        assert!(r#move.is_check());
+        assert!(!r#move.is_capture());
+        assert!(!r#move.is_checkmate());
+        assert!(!r#move.is_promotion());
+
+        assert!("O-O+".parse::<Turn>().unwrap().is_check());
+        assert!("O-O-O#".parse::<Turn>().unwrap().is_checkmate());
+        assert!(!"O-O".parse::<Turn>().unwrap().is_check());
+    }
+    #[test]
+    fn move_constructor() {
+        assert_eq!(
+            Move::new(PieceType::Pawn, Square::E4),
+            Move {
+                piece: PieceType::Pawn,
+                dst: Square::E4,
+                flags: flags::NONE,
+                src: None,
+                promotion: None,
+            }
+        );
+        let Turn::Move(parsed) = "exf8=R#".parse::<Turn>().unwrap() else {
+            panic!("Not a move");
+        };
+        assert_eq!(
+            Move::new(PieceType::Pawn, Square::F8)
+                .from(Source::Line(Line::FileE))
+                .promote(PieceType::Rook)
+                .with_flags(flags::CAPTURE | flags::CHECKMATE),
+            parsed
+        );
+    }
+    #[test]
+    fn undecorated_display() {
+        let mate = "exf8=R#".parse::<Turn>().unwrap();
+        assert_eq!(mate.to_string(), "exf8=R#");
+        assert_eq!(format!("{mate:#}"), "ef8=R");
+        assert!(!format!("{mate:#}").contains('#'));
+        assert_eq!(format!("{:#}", "Nbd2+".parse::<Turn>().unwrap()), "Nbd2");
+        assert_eq!(format!("{:#}", "O-O-O+".parse::<Turn>().unwrap()), "0-0-0");
+        assert_eq!(format!("{:#}", Turn::Null), "--");
+    }
+}
@@ -1,4 +1,5 @@
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The type of castling
 pub enum CastlingType {
     /// Queenside castling
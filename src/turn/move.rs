@@ -1,7 +1,9 @@
+use super::flags;
 use crate::board::{Source, Square};
 use crate::pieces::PieceType;
 
 #[derive(Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The information specifiying a piece move
 pub struct Move {
     /// The type of piece that is moving
@@ -15,3 +17,52 @@ pub struct Move {
     /// The piece that a pawn promotes to
     pub promotion: Option<PieceType>,
 }
+
+impl Move {
+    /// Creates a move of the piece to the destination square, with no source, flags or promotion
+    pub fn new(piece: PieceType, dst: Square) -> Move {
+        Move {
+            piece,
+            dst,
+            flags: 0,
+            src: None,
+            promotion: None,
+        }
+    }
+    /// Returns the move with the provided source for disambiguation
+    pub fn from(self, src: Source) -> Move {
+        Move {
+            src: Some(src),
+            ..self
+        }
+    }
+    /// Returns the move with the provided promotion piece
+    pub fn promote(self, piece: PieceType) -> Move {
+        Move {
+            promotion: Some(piece),
+            ..self
+        }
+    }
+    /// Returns the move with the provided capture/check/checkmate flags, replacing the current
+    /// ones
+    pub fn with_flags(self, flags: u8) -> Move {
+        Move { flags, ..self }
+    }
+    /// Returns whether the move is flagged as a capture
+    pub fn is_capture(&self) -> bool {
+        self.flags & flags::CAPTURE != 0
+    }
+    /// Returns whether the move is flagged as giving check, a checkmate is only flagged by
+    /// [Move::is_checkmate]
+    pub fn is_check(&self) -> bool {
+        self.flags & flags::CHECK != 0
+    }
+    /// Returns whether the move is flagged as giving checkmate
+    pub fn is_checkmate(&self) -> bool {
+        self.flags & flags::CHECKMATE != 0
+    }
+    /// Returns whether the move promotes a pawn
+    pub fn is_promotion(&self) -> bool {
+        self.promotion.is_some()
+    }
+}
@@ -1,18 +1,19 @@
+use alloc::collections::BTreeMap;
+#[cfg(feature = "std")]
 use std::error::Error;
-use std::{collections::HashMap, hash::Hash};
 
 #[derive(Debug, Clone)]
 /// Struct that counts the number of times an object is added to it
-pub struct Counter<Key: Hash + Eq> {
-    map: HashMap<Key, usize>,
+pub struct Counter<Key: Ord> {
+    map: BTreeMap<Key, usize>,
 }
 
 #[allow(dead_code)]
-impl<Key: Hash + Eq> Counter<Key> {
+impl<Key: Ord> Counter<Key> {
     /// Creates a new empty counter
     pub fn new() -> Counter<Key> {
         Counter {
-            map: HashMap::new(),
+            map: BTreeMap::new(),
         }
     }
     /// Adds an element to the counter, incrementing the count if it was seen before and setting
@@ -24,6 +25,16 @@ impl<Key: Hash + Eq> Counter<Key> {
             self.map.insert(key, 1);
         }
     }
+    /// Removes an element from the counter, decrementing the count and dropping the element once
+    /// the count reaches 0. Does nothing if the element was not seen
+    pub fn remove(&mut self, key: &Key) {
+        if let Some(count) = self.map.get_mut(key) {
+            *count -= 1;
+            if *count == 0 {
+                self.map.remove(key);
+            }
+        }
+    }
     /// Creates a counter from an iterable, where each element of the iterator will be counted
     pub fn from(collection: impl IntoIterator<Item = Key>) -> Counter<Key> {
         let mut counter = Counter::new();
@@ -36,27 +47,56 @@ impl<Key: Hash + Eq> Counter<Key> {
     pub fn counts(&self) -> impl Iterator<Item = &usize> {
         self.map.values()
     }
+    /// Returns an iterator over the elements of the Counter along with their counts, in
+    /// ascending order of the elements
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, usize)> {
+        self.map.iter().map(|(key, &count)| (key, count))
+    }
     /// Returns the count of the provided key, returns 0 if the element was not seen yet
     pub fn get(&self, key: &Key) -> usize {
         *self.map.get(key).unwrap_or(&0)
     }
+    /// Returns the element with the highest count along with its count, returns `None` if the
+    /// counter is empty
+    ///
+    /// If several elements share the highest count, any one of them may be returned
+    pub fn most_common(&self) -> Option<(&Key, usize)> {
+        self.map
+            .iter()
+            .max_by_key(|(_, &count)| count)
+            .map(|(key, &count)| (key, count))
+    }
+    /// Returns the highest count of any element, returns 0 if the counter is empty
+    pub fn max_count(&self) -> usize {
+        self.counts().copied().max().unwrap_or(0)
+    }
+    /// Returns the total number of elements added to the counter
+    pub fn total(&self) -> usize {
+        self.map.values().sum()
+    }
 }
 
-impl<Key: Hash + Eq> Default for Counter<Key> {
+impl<Key: Ord> Default for Counter<Key> {
     fn default() -> Self {
         Counter::new()
     }
 }
 
-/// Utility function that prints out errors, including their source
-pub fn print_all_errors<T: Error + ?Sized>(err: &T) {
-    println!("{}", err);
-    let mut next = err.source();
-    while let Some(e) = next {
-        println!("{}", e);
-        next = e.source();
+impl<Key: Ord> FromIterator<Key> for Counter<Key> {
+    fn from_iter<I: IntoIterator<Item = Key>>(iter: I) -> Self {
+        Counter::from(iter)
+    }
+}
+
+impl<Key: Ord> Extend<Key> for Counter<Key> {
+    fn extend<I: IntoIterator<Item = Key>>(&mut self, iter: I) {
+        for item in iter {
+            self.add(item);
+        }
     }
 }
+
+#[cfg(feature = "std")]
 /// Utility function that returns a string of all errors, including their source
 pub fn all_errors_string<T: Error + ?Sized>(err: &T) -> String {
     let mut error_string = format!("{err}");
@@ -67,3 +107,72 @@ pub fn all_errors_string<T: Error + ?Sized>(err: &T) -> String {
     }
     error_string
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_remove() {
+        let mut counter = Counter::from([1, 2, 2, 3, 3, 3]);
+        assert_eq!(counter.total(), 6);
+        counter.add(4);
+        counter.remove(&4);
+        assert_eq!(counter.get(&4), 0);
+        assert_eq!(counter.total(), 6);
+        counter.remove(&3);
+        assert_eq!(counter.get(&3), 2);
+        counter.remove(&1);
+        assert_eq!(counter.get(&1), 0);
+        assert_eq!(counter.counts().count(), 2);
+        assert_eq!(counter.total(), 4);
+    }
+    #[test]
+    fn most_common() {
+        let counter = Counter::from(["a", "b", "b", "c", "c", "d"]);
+        let (key, count) = counter.most_common().unwrap();
+        assert!(*key == "b" || *key == "c");
+        assert_eq!(count, 2);
+        assert_eq!(counter.max_count(), 2);
+
+        let counter = Counter::from([1, 2, 2, 2]);
+        assert_eq!(counter.most_common(), Some((&2, 3)));
+        assert_eq!(counter.max_count(), 3);
+
+        let counter: Counter<u64> = Counter::new();
+        assert_eq!(counter.most_common(), None);
+        assert_eq!(counter.max_count(), 0);
+    }
+    #[test]
+    fn remove_absent() {
+        let mut counter = Counter::from(["a"]);
+        counter.remove(&"b");
+        assert_eq!(counter.get(&"a"), 1);
+        assert_eq!(counter.get(&"b"), 0);
+        assert_eq!(counter.total(), 1);
+
+        let mut counter: Counter<&str> = Counter::new();
+        counter.remove(&"a");
+        assert_eq!(counter.total(), 0);
+    }
+    #[test]
+    fn collect_extend() {
+        let mut counter: Counter<&str> = ["a", "a", "b"].into_iter().collect();
+        assert_eq!(counter.get(&"a"), 2);
+        assert_eq!(counter.get(&"b"), 1);
+        counter.extend(["b", "c"]);
+        assert_eq!(counter.get(&"b"), 2);
+        assert_eq!(counter.get(&"c"), 1);
+        assert_eq!(counter.total(), 5);
+    }
+    #[test]
+    fn iter() {
+        use alloc::{vec, vec::Vec};
+        let counter = Counter::from(["b", "a", "b", "c", "b"]);
+        assert_eq!(
+            counter.iter().collect::<Vec<_>>(),
+            vec![(&"a", 1), (&"b", 3), (&"c", 1)]
+        );
+        assert_eq!(Counter::<u8>::new().iter().count(), 0);
+    }
+}